@@ -4,3 +4,38 @@ use crate::vector::Vector;
 pub trait AsVectorRef<'v, 't> {
     fn as_vec_ref(&self) -> Vector<'v, 't>;
 }
+
+impl<'v, 't> AsVectorRef<'v, 't> for (&'t str, &'v [f32]) {
+    #[inline]
+    fn as_vec_ref(&self) -> Vector<'v, 't> {
+        Vector::new(self.1, self.0)
+    }
+}
+
+/// Raw vector data without a term.
+impl<'v> AsVectorRef<'v, 'static> for &'v [f32] {
+    #[inline]
+    fn as_vec_ref(&self) -> Vector<'v, 'static> {
+        Vector::new(self, "")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tuple_as_vec_ref() {
+        let data = [1.0, 2.0, 3.0];
+        let vec = Vector::new(&[3.0, 2.0, 1.0], "b");
+
+        let tuple = ("a", &data[..]);
+        assert_eq!(tuple.as_vec_ref(), Vector::new(&data, "a"));
+        assert_eq!(vec.dot(&tuple), 10.0);
+        assert_eq!(vec.cosine(&tuple), vec.cosine(&Vector::new(&data, "a")));
+
+        let raw = &data[..];
+        assert_eq!(raw.as_vec_ref(), Vector::new(&data, ""));
+        assert_eq!(vec.dot(&raw), 10.0);
+    }
+}
@@ -0,0 +1,21 @@
+/// Differences between two [`VecSpace`](crate::space::VecSpace)s, created with
+/// [`VecSpace::diff`](crate::space::VecSpace::diff).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpaceDiff<'a> {
+    /// Terms which only exist in the other space.
+    pub added: Vec<&'a str>,
+
+    /// Terms which only exist in the original space.
+    pub removed: Vec<&'a str>,
+
+    /// Terms which exist in both spaces but whose vectors differ.
+    pub changed: Vec<&'a str>,
+}
+
+impl<'a> SpaceDiff<'a> {
+    /// Returns `true` if both spaces contain the same terms with equal vectors.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
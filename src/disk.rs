@@ -0,0 +1,138 @@
+use crate::{error::Error, parse::Word2VecParser, vector::OwnedVector};
+use ahash::AHashMap;
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Read-only access to the vectors of a binary word2vec file without loading them into memory.
+/// Only the terms and the positions of their vectors in the file are kept in memory, the vectors
+/// themselves are read from the file on each lookup.
+#[derive(Debug)]
+pub struct DiskVecSpace<R> {
+    reader: BufReader<R>,
+    dimension: usize,
+
+    /// Maps terms to the offset of their vector data in the file.
+    offsets: AHashMap<String, u64>,
+}
+
+impl DiskVecSpace<File> {
+    /// Opens the binary word2vec file at `path` and indexes its terms.
+    #[inline]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> DiskVecSpace<R> {
+    /// Indexes the terms of all vectors in `reader`, which has to contain vectors in binary
+    /// word2vec format including the header. This reads the whole file once.
+    pub fn new(reader: R) -> Result<Self, Error> {
+        let parser = Word2VecParser::new().binary();
+        let mut r = BufReader::new(reader);
+
+        let mut line_buf = vec![];
+        let mut float_buf = vec![];
+
+        let (header, _) = parser.read_header(&mut r, &mut line_buf)?;
+        let (count, dimension) = header.ok_or(Error::InvalidVectorFormat)?;
+        let data_len = (dimension * std::mem::size_of::<f32>()) as u64;
+
+        let mut offsets = AHashMap::with_capacity(count);
+        loop {
+            let vec = parser.parse_vec(&mut r, &mut float_buf, &mut line_buf, dimension);
+            if vec == Err(Error::EOF) {
+                break;
+            }
+            let vec = vec?;
+
+            let end = r.stream_position()?;
+            offsets.insert(vec.term().to_string(), end - data_len);
+        }
+
+        Ok(Self {
+            reader: r,
+            dimension,
+            offsets,
+        })
+    }
+
+    /// Reads the vector of the given term from the file. Returns `None` if the term doesn't
+    /// exist or reading the vector failed. If a term occurs multiple times, the last vector is
+    /// returned.
+    pub fn get_term(&mut self, term: &str) -> Option<OwnedVector> {
+        let offset = *self.offsets.get(term)?;
+        self.reader.seek(SeekFrom::Start(offset)).ok()?;
+
+        let mut data = Vec::with_capacity(self.dimension);
+        let mut float_buf = [0u8; 4];
+        for _ in 0..self.dimension {
+            self.reader.read_exact(&mut float_buf).ok()?;
+            data.push(f32::from_le_bytes(float_buf));
+        }
+
+        Some(OwnedVector::new_raw(data, term.to_string()))
+    }
+
+    /// Returns `true` if the file contains a vector for `term`.
+    #[inline]
+    pub fn contains(&self, term: &str) -> bool {
+        self.offsets.contains_key(term)
+    }
+
+    /// Amount of distinct terms in the file.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns `true` if the file contains no vectors.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// The dimension of the vectors.
+    #[inline]
+    pub fn dim(&self) -> usize {
+        self.dimension
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{export::Exporter, space::VecSpace, vector::Vector};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_disk_space() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, 3.4], "term2"),
+            Vector::new(&[3.1, 9.4, 3.0], "term3"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        let mut buf = vec![];
+        Exporter::new(&mut buf)
+            .use_binary()
+            .export_space(&space)
+            .unwrap();
+
+        let mut disk = DiskVecSpace::new(Cursor::new(buf)).unwrap();
+
+        assert_eq!(disk.len(), 3);
+        assert_eq!(disk.dim(), 3);
+
+        // Read in a different order than stored to require seeking.
+        for vec in vecs.iter().rev() {
+            assert_eq!(disk.get_term(vec.term()).unwrap().as_ref(), *vec);
+        }
+        assert!(disk.contains("term2"));
+        assert_eq!(disk.get_term("term4"), None);
+    }
+}
@@ -7,6 +7,10 @@ pub enum Error {
     Io(std::io::Error),
     Utf8Error(Utf8Error),
     DimMismatch(usize, usize),
+    MissingDimension,
+    IndexOverflow,
+    InvalidPermutation,
+    LengthMismatch(usize, usize),
 }
 
 impl PartialEq for Error {
@@ -32,8 +36,63 @@ impl From<std::io::Error> for Error {
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        match self {
+            Error::InvalidVectorFormat => write!(f, "invalid vector format"),
+            Error::EOF => write!(f, "unexpected end of file"),
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Utf8Error(err) => write!(f, "invalid utf-8: {err}"),
+            Error::DimMismatch(got, exp) => {
+                write!(f, "dimension mismatch: got {got}, expected {exp}")
+            }
+            Error::MissingDimension => write!(f, "missing vector dimension"),
+            Error::IndexOverflow => write!(f, "too many vectors to index"),
+            Error::InvalidPermutation => write!(f, "invalid permutation"),
+            Error::LengthMismatch(got, exp) => {
+                write!(f, "length mismatch: got {got} elements, expected {exp}")
+            }
+        }
     }
 }
 
 impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Error::InvalidVectorFormat.to_string(),
+            "invalid vector format"
+        );
+        assert_eq!(Error::EOF.to_string(), "unexpected end of file");
+        assert_eq!(
+            Error::DimMismatch(3, 300).to_string(),
+            "dimension mismatch: got 3, expected 300"
+        );
+
+        assert_eq!(
+            Error::MissingDimension.to_string(),
+            "missing vector dimension"
+        );
+        assert_eq!(
+            Error::IndexOverflow.to_string(),
+            "too many vectors to index"
+        );
+        assert_eq!(Error::InvalidPermutation.to_string(), "invalid permutation");
+        assert_eq!(
+            Error::LengthMismatch(2, 3).to_string(),
+            "length mismatch: got 2 elements, expected 3"
+        );
+
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        assert_eq!(Error::Io(io).to_string(), "io error: no such file");
+
+        let utf8 = String::from_utf8(vec![0xff]).unwrap_err().utf8_error();
+        assert_eq!(
+            Error::Utf8Error(utf8).to_string(),
+            format!("invalid utf-8: {utf8}")
+        );
+    }
+}
@@ -1,17 +1,29 @@
 use crate::{space::VecSpace, vector::Vector};
-use std::io::Write;
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
 
 pub const DEFAULT_WRITE_HEADER: bool = true;
 pub const DEFAULT_TERM_SEP: char = ' ';
 pub const DEFAULT_VEC_SEP: char = ' ';
 
+/// Amount of digits the vector count in the header of a [`StreamingExporter`] gets padded to. This
+/// allows overwriting the count later on without moving the vectors written after the header.
+const HEADER_COUNT_WIDTH: usize = 20;
+
 /// Exporter for vectors
 #[derive(Debug, Clone, Copy)]
 pub struct Exporter<W> {
     // Options
+    write_header: bool,
     term_separator: char,
     vec_separator: char,
     binary: bool,
+    length_prefixed_terms: bool,
+    #[cfg(feature = "half")]
+    f16: bool,
 
     // Where to write the data to
     writer: W,
@@ -24,19 +36,71 @@ impl<W> Exporter<W> {
     #[inline]
     pub fn new(w: W) -> Self {
         Self {
+            write_header: DEFAULT_WRITE_HEADER,
             term_separator: DEFAULT_TERM_SEP,
             vec_separator: DEFAULT_VEC_SEP,
             binary: false,
+            length_prefixed_terms: false,
+            #[cfg(feature = "half")]
+            f16: false,
             writer: w,
             header_written: false,
         }
     }
 
+    /// Create a new vector exporter which appends vectors to a writer which already contains
+    /// exported vectors and a header. No header will be written by this exporter, so
+    /// [`Exporter::export_vectors`] can be called directly.
+    ///
+    /// Keeping the vector count in the existing header accurate is the responsibility of the
+    /// caller.
+    #[inline]
+    pub fn append(w: W) -> Self {
+        let mut exporter = Self::new(w);
+        exporter.header_written = true;
+        exporter
+    }
+
+    /// Doesn't write a header line, as used by the GloVe text format. Each vector is written on its
+    /// own line, terminated by a newline.
+    pub fn no_header(mut self) -> Self {
+        self.write_header = false;
+        self
+    }
+
     /// Exports the data into binary word2vec format.
     pub fn use_binary(mut self) -> Self {
         self.binary = true;
         self
     }
+
+    /// Prefixes each term with its length in bytes as little endian `u16` instead of terminating
+    /// it with a space, so terms can contain any character. Only used in binary format. The
+    /// vectors can be parsed using [`Word2VecParser::length_prefixed_terms`].
+    ///
+    /// [`Word2VecParser::length_prefixed_terms`]: crate::parse::Word2VecParser::length_prefixed_terms
+    pub fn length_prefixed_terms(mut self) -> Self {
+        self.length_prefixed_terms = true;
+        self
+    }
+
+    /// Exports the data into binary word2vec format, storing each component as 2 byte
+    /// half-float. This loses precision but halves the size of the vector data.
+    #[cfg(feature = "half")]
+    pub fn use_binary_f16(mut self) -> Self {
+        self.binary = true;
+        self.f16 = true;
+        self
+    }
+}
+
+impl Exporter<BufWriter<File>> {
+    /// Create a new vector exporter which writes the vectors buffered into a newly created file
+    /// at `path`. An existing file will be truncated.
+    #[inline]
+    pub fn to_file<P: AsRef<Path>>(path: P) -> Result<Self, std::io::Error> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
 }
 
 impl<W: Write> Exporter<W> {
@@ -55,20 +119,49 @@ impl<W: Write> Exporter<W> {
     where
         F: Fn(&Vector) -> bool,
     {
-        let mut n = 0;
+        let mut n = self.write_space_header(space.len(), space.dim(), 0)?;
+        n += self.export_vectors(space.iter().filter(|i| (filter)(i)))?;
+        self.writer.flush()?;
+        Ok(n)
+    }
 
-        let len = space.len();
-        let dim = space.dim();
-        n += self.write_header(len, dim)?;
+    /// Exports all vectors from a [`VecSpace`] with a cosine similarity of at least `radius` to
+    /// `query`.
+    pub fn export_neighborhood(
+        mut self,
+        space: &VecSpace,
+        query: &Vector,
+        radius: f32,
+    ) -> Result<usize, std::io::Error> {
+        let neighbors: Vec<_> = space.iter().filter(|i| i.cosine(query) >= radius).collect();
+
+        let mut n = self.write_space_header(neighbors.len(), space.dim(), 0)?;
+        n += self.export_vectors(neighbors)?;
+        self.writer.flush()?;
+        Ok(n)
+    }
 
-        // In txt format, vectors always prepend a '\n' but in binary this is not necessary, so add
-        // one after the header as this is needed for binary too.
-        if self.binary {
-            n += self.writer.write(b"\n")?;
-        }
+    /// Exports `n` randomly picked vectors from a [`VecSpace`]. Using the same `seed` always picks
+    /// the same vectors. If the space has less than `n` vectors, all vectors get exported.
+    #[cfg(feature = "rand")]
+    pub fn export_sample(
+        mut self,
+        space: &VecSpace,
+        n: usize,
+        seed: u64,
+    ) -> Result<usize, std::io::Error> {
+        use rand::{rngs::StdRng, SeedableRng};
 
-        n += self.export_vectors(space.iter().filter(|i| (filter)(i)))?;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let amount = n.min(space.len());
 
+        // Keep the original order of the sampled vectors.
+        let mut indices = rand::seq::index::sample(&mut rng, space.len(), amount).into_vec();
+        indices.sort_unstable();
+
+        let mut n = self.write_space_header(amount, space.dim(), 0)?;
+        n += self.export_vectors(indices.into_iter().filter_map(|i| space.get(i)))?;
+        self.writer.flush()?;
         Ok(n)
     }
 
@@ -80,7 +173,7 @@ impl<W: Write> Exporter<W> {
     where
         I: IntoIterator<Item = Vector<'a, 'b>>,
     {
-        if !self.header_written {
+        if self.write_header && !self.header_written {
             panic!("Expecetd header to be written");
         }
 
@@ -105,10 +198,28 @@ impl<W: Write> Exporter<W> {
     /// Write a single vector in bin format.
     fn write_vector_bin(&mut self, vec: Vector) -> Result<usize, std::io::Error> {
         let mut n = 0;
-        n += self.writer.write(vec.term().as_bytes())?;
-        n += self.writer.write(&[b' '])?;
+
+        if self.length_prefixed_terms {
+            let len = u16::try_from(vec.term().len()).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "term too long")
+            })?;
+            n += self.writer.write(&len.to_le_bytes())?;
+            n += self.writer.write(vec.term().as_bytes())?;
+        } else {
+            n += self.writer.write(vec.term().as_bytes())?;
+            n += self.writer.write(b" ")?;
+        }
+
+        #[cfg(feature = "half")]
+        if self.f16 {
+            for v in vec.data() {
+                n += self.writer.write(&half::f16::from_f32(*v).to_le_bytes())?;
+            }
+            return Ok(n);
+        }
+
         for v in vec.data() {
-            self.writer.write(&v.to_le_bytes())?;
+            n += self.writer.write(&v.to_le_bytes())?;
         }
         Ok(n)
     }
@@ -116,7 +227,11 @@ impl<W: Write> Exporter<W> {
     /// Write a single vector in txt format.
     fn write_vector_txt(&mut self, vec: Vector) -> Result<usize, std::io::Error> {
         let mut n = 0;
-        n += self.writer.write(b"\n")?;
+        // Without a header there is no previous line to terminate, so the newline gets written
+        // after the vector instead.
+        if self.write_header {
+            n += self.writer.write(b"\n")?;
+        }
         // Term itself
         n += self.writer.write(vec.term().as_bytes())?;
         // Term separator
@@ -134,18 +249,132 @@ impl<W: Write> Exporter<W> {
             n += self.writer.write(v.to_string().as_bytes())?;
         }
 
+        if !self.write_header {
+            n += self.writer.write(b"\n")?;
+        }
+
         Ok(n)
     }
 
-    /// Writes the header line.
-    fn write_header(&mut self, dim: usize, len: usize) -> Result<usize, std::io::Error> {
+    /// Writes the header line for `len` vectors with `dim` dimensions that will be written
+    /// afterwards.
+    pub(crate) fn write_space_header(
+        &mut self,
+        len: usize,
+        dim: usize,
+        count_width: usize,
+    ) -> Result<usize, std::io::Error> {
+        if !self.write_header {
+            return Ok(0);
+        }
+
+        let mut n = self.write_header(len, dim, count_width)?;
+
+        // In txt format, vectors always prepend a '\n' but in binary this is not necessary, so add
+        // one after the header as this is needed for binary too.
+        if self.binary {
+            n += self.writer.write(b"\n")?;
+        }
+
+        Ok(n)
+    }
+
+    /// Writes the header line `<len> <dim>` as done by the original word2vec tool. The vector
+    /// count gets padded with zeros to `count_width` digits.
+    fn write_header(
+        &mut self,
+        len: usize,
+        dim: usize,
+        count_width: usize,
+    ) -> Result<usize, std::io::Error> {
         self.header_written = true;
         let mut n = 0;
-        n += self.writer.write(dim.to_string().as_bytes())?;
+        n += self
+            .writer
+            .write(format!("{len:0count_width$}").as_bytes())?;
         n += self.writer.write(b" ")?;
-        n += self.writer.write(len.to_string().as_bytes())?;
+        n += self.writer.write(dim.to_string().as_bytes())?;
+        Ok(n)
+    }
+}
+
+impl<W: Write + Seek> Exporter<W> {
+    /// Starts exporting vectors with `dim` dimensions one by one without having to know the amount
+    /// of vectors upfront. A placeholder header gets written which will be replaced with the real
+    /// header when calling [`StreamingExporter::finish`].
+    ///
+    /// This requires the writer to be seekable. For other writers the amount of vectors has to be
+    /// known before writing the header, as done in [`Exporter::export_space`].
+    pub fn start(mut self, dim: usize) -> Result<StreamingExporter<W>, std::io::Error> {
+        let header_pos = self.writer.stream_position()?;
+
+        // Pad the count so the real one fits in the same space later on.
+        let n = self.write_space_header(0, dim, HEADER_COUNT_WIDTH)?;
+
+        Ok(StreamingExporter {
+            exporter: self,
+            header_pos,
+            dim,
+            count: 0,
+            n,
+        })
+    }
+}
+
+/// Exporter writing vectors one by one. Created by [`Exporter::start`].
+#[derive(Debug)]
+pub struct StreamingExporter<W> {
+    exporter: Exporter<W>,
+    header_pos: u64,
+    dim: usize,
+    count: usize,
+
+    /// Amount of bytes written so far
+    n: usize,
+}
+
+impl<W: Write + Seek> StreamingExporter<W> {
+    /// Writes a single vector. Returns an error of kind [`std::io::ErrorKind::InvalidInput`] if
+    /// the dimension of the vector doesn't match the dimension passed to [`Exporter::start`].
+    pub fn push(&mut self, vec: Vector) -> Result<usize, std::io::Error> {
+        if vec.dim() != self.dim {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "vector dimension mismatch",
+            ));
+        }
+        let n = self.exporter.write_vector(vec)?;
+        self.count += 1;
+        self.n += n;
         Ok(n)
     }
+
+    /// Amount of vectors written so far.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Writes the real header and finishes the export. Returns the total amount of bytes written.
+    pub fn finish(mut self) -> Result<usize, std::io::Error> {
+        if !self.exporter.write_header {
+            self.exporter.writer.flush()?;
+            return Ok(self.n);
+        }
+
+        let writer = &mut self.exporter.writer;
+        let end = writer.stream_position()?;
+
+        writer.seek(SeekFrom::Start(self.header_pos))?;
+        self.exporter
+            .write_header(self.count, self.dim, HEADER_COUNT_WIDTH)?;
+
+        let writer = &mut self.exporter.writer;
+        writer.seek(SeekFrom::Start(end))?;
+        writer.flush()?;
+
+        Ok(self.n)
+    }
 }
 
 #[cfg(test)]
@@ -173,6 +402,88 @@ mod test {
         assert_eq!(space, parsed);
     }
 
+    #[test]
+    fn test_file_export() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, 3.4], "term2"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        // Unique name so concurrent test runs don't interfere with each other.
+        let name = format!("word_vec_file_export_{}.vec", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let exported = Exporter::to_file(&file).and_then(|i| i.export_space(&space));
+        let parsed = Word2VecParser::new().parse_file(&file);
+        // The file doesn't exist if creating it failed, which is reported below.
+        let _ = std::fs::remove_file(&file);
+
+        exported.unwrap();
+        assert_eq!(space, parsed.unwrap());
+
+        // Keep the buffered writer alive while reading the file, so the export has to flush it.
+        let mut writer = BufWriter::new(File::create(&file).unwrap());
+        let exported = Exporter::new(&mut writer).export_space(&space);
+        let content = std::fs::read(&file);
+        drop(writer);
+        let _ = std::fs::remove_file(&file);
+
+        assert_eq!(exported.unwrap(), content.as_ref().unwrap().len());
+        let parsed = Word2VecParser::new().parse(Cursor::new(content.unwrap()));
+        assert_eq!(space, parsed.unwrap());
+    }
+
+    #[test]
+    fn test_no_header_export() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, 3.4], "term2"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        let mut buf: Vec<u8> = vec![];
+        let n = Exporter::new(&mut buf)
+            .no_header()
+            .export_space(&space)
+            .unwrap();
+
+        assert_eq!(n, buf.len());
+        assert_eq!(
+            std::str::from_utf8(&buf).unwrap(),
+            "term1 1.2 2 4.4\nterm2 2.3 1 3.4\n"
+        );
+
+        let parsed = Word2VecParser::new()
+            .no_header()
+            .parse(Cursor::new(&buf))
+            .unwrap();
+        assert_eq!(space, parsed);
+    }
+
+    #[test]
+    fn test_neighborhood_export() {
+        let vecs = [
+            Vector::new(&[1.0, 0.0, 0.0], "term1"),
+            Vector::new(&[0.9, 0.1, 0.0], "term2"),
+            Vector::new(&[0.0, 1.0, 0.0], "term3"),
+            Vector::new(&[1.0, 0.0, 0.1], "term4"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        let mut buf: Vec<u8> = vec![];
+        Exporter::new(&mut buf)
+            .export_neighborhood(&space, &vecs[0], 0.9)
+            .unwrap();
+
+        let parsed = Word2VecParser::new().parse(Cursor::new(&buf)).unwrap();
+        let terms: Vec<_> = parsed.terms().collect();
+        assert_eq!(terms, ["term1", "term2", "term4"]);
+        assert_eq!(parsed.declared_len(), Some(3));
+    }
+
     #[test]
     fn test_bin_export() {
         let vecs = [
@@ -197,4 +508,249 @@ mod test {
 
         assert_eq!(space, parsed);
     }
+
+    #[test]
+    fn test_binary_size_estimate() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, 3.4], "t2"),
+            Vector::new(&[3.1, 9.4, 3.0], "term3"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        let mut buf: Vec<u8> = vec![];
+        let n = Exporter::new(&mut buf)
+            .use_binary()
+            .export_space(&space)
+            .unwrap();
+
+        assert_eq!(space.binary_size_estimate(), n);
+        assert_eq!(space.binary_size_estimate(), buf.len());
+    }
+
+    #[test]
+    fn test_append_export() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, 3.4], "term2"),
+            Vector::new(&[3.1, 9.4, 3.0], "term3"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(&vecs[..2]);
+
+        let mut all = space.clone();
+        all.extend(&vecs[2..]);
+
+        for binary in [false, true] {
+            let mut buf: Vec<u8> = vec![];
+            let mut exporter = Exporter::new(&mut buf);
+            if binary {
+                exporter = exporter.use_binary();
+            }
+            exporter.export_space(&space).unwrap();
+
+            let mut exporter = Exporter::append(&mut buf);
+            if binary {
+                exporter = exporter.use_binary();
+            }
+            exporter.export_vectors(vecs[2..].iter().copied()).unwrap();
+
+            // Correct the count in the header.
+            assert!(buf.starts_with(b"2 3"));
+            buf[0] = b'3';
+
+            let mut parser = Word2VecParser::new();
+            if binary {
+                parser = parser.binary();
+            }
+            assert_eq!(parser.parse(Cursor::new(&buf)).unwrap(), all);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "half")]
+    fn test_bin_f16_export() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, -3.4], "term2"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        let mut buf: Vec<u8> = vec![];
+        let n = Exporter::new(&mut buf)
+            .use_binary_f16()
+            .export_space(&space)
+            .unwrap();
+        assert_eq!(n, buf.len());
+
+        let parsed = Word2VecParser::new()
+            .f16_binary()
+            .parse(Cursor::new(&buf))
+            .unwrap();
+
+        assert_eq!(parsed.len(), space.len());
+        for (vec, orig) in parsed.iter().zip(vecs) {
+            assert_eq!(vec.term(), orig.term());
+            for (a, b) in vec.data().iter().zip(orig.data()) {
+                assert!((a - b).abs() < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn test_length_prefixed_export() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term with spaces"),
+            Vector::new(&[2.3, 1.0, 3.4], "multi\nline\n"),
+            Vector::new(&[3.1, 9.4, 3.0], ""),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        let mut buf: Vec<u8> = vec![];
+        let n = Exporter::new(&mut buf)
+            .use_binary()
+            .length_prefixed_terms()
+            .export_space(&space)
+            .unwrap();
+        assert_eq!(n, buf.len());
+
+        let parsed = Word2VecParser::new()
+            .binary()
+            .length_prefixed_terms()
+            .parse(Cursor::new(&buf))
+            .unwrap();
+        assert_eq!(parsed, space);
+
+        let long_term = "a".repeat(u16::MAX as usize + 1);
+        let mut space = VecSpace::new(1);
+        space.insert(Vector::new(&[1.0], &long_term)).unwrap();
+        let res = Exporter::new(vec![])
+            .use_binary()
+            .length_prefixed_terms()
+            .export_space(&space);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_bin_export_header() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, 3.4], "term2"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        let mut buf: Vec<u8> = vec![];
+        let n = Exporter::new(&mut buf)
+            .use_binary()
+            .export_space(&space)
+            .unwrap();
+        assert_eq!(n, buf.len());
+
+        // Same header as written by the original word2vec tool, directly followed by the vectors.
+        assert!(buf.starts_with(b"2 3\nterm1 "));
+        assert_eq!(buf.len(), 4 + 2 * (6 + 3 * 4));
+
+        // The original word2vec tool also writes a '\n' after each vector.
+        let mut c_buf = b"2 3\n".to_vec();
+        for vec in vecs {
+            c_buf.extend_from_slice(vec.term().as_bytes());
+            c_buf.push(b' ');
+            for v in vec.data() {
+                c_buf.extend_from_slice(&v.to_le_bytes());
+            }
+            c_buf.push(b'\n');
+        }
+
+        let parser = Word2VecParser::new().binary();
+        assert_eq!(parser.parse(Cursor::new(&buf)).unwrap(), space);
+        assert_eq!(parser.parse(Cursor::new(&c_buf)).unwrap(), space);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_sample_export() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, 3.4], "term2"),
+            Vector::new(&[3.1, 9.4, 3.0], "term3"),
+            Vector::new(&[0.1, 0.4, 1.0], "term4"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        let export = |n: usize, seed: u64| {
+            let mut buf: Vec<u8> = vec![];
+            Exporter::new(&mut buf)
+                .export_sample(&space, n, seed)
+                .unwrap();
+            buf
+        };
+
+        assert_eq!(export(2, 42), export(2, 42));
+
+        for n in [0, 2, 4, 10] {
+            let buf = export(n, 7);
+            let header = std::str::from_utf8(&buf).unwrap().lines().next().unwrap();
+            assert_eq!(header, format!("{} 3", n.min(space.len())));
+
+            let parsed = Word2VecParser::new().parse(Cursor::new(&buf)).unwrap();
+            assert_eq!(parsed.len(), n.min(space.len()));
+        }
+    }
+
+    #[test]
+    fn test_streaming_export() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, 3.4], "term2"),
+            Vector::new(&[3.1, 9.4, 3.0], "term3"),
+        ];
+        let mut space = VecSpace::new(3);
+        space.extend(vecs);
+
+        for binary in [false, true] {
+            let mut buf = Cursor::new(vec![]);
+
+            let mut exporter = Exporter::new(&mut buf);
+            if binary {
+                exporter = exporter.use_binary();
+            }
+            let mut stream = exporter.start(3).unwrap();
+            for vec in vecs {
+                stream.push(vec).unwrap();
+            }
+            assert_eq!(stream.count(), 3);
+            stream.finish().unwrap();
+
+            let mut parser = Word2VecParser::new();
+            if binary {
+                parser = parser.binary();
+            }
+            let parsed = parser.parse(Cursor::new(buf.into_inner())).unwrap();
+            assert_eq!(space, parsed);
+        }
+    }
+
+    #[test]
+    fn test_streaming_export_dim_mismatch() {
+        let mut buf = Cursor::new(vec![]);
+        let mut stream = Exporter::new(&mut buf).start(3).unwrap();
+
+        let err = stream.push(Vector::new(&[1.0, 2.0], "a")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(stream.count(), 0);
+
+        stream.push(Vector::new(&[1.0, 2.0, 3.0], "b")).unwrap();
+        stream.finish().unwrap();
+
+        let parsed = Word2VecParser::new()
+            .parse(Cursor::new(buf.into_inner()))
+            .unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed.get(0).unwrap().term(), "b");
+    }
 }
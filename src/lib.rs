@@ -1,7 +1,14 @@
 pub mod as_vector;
+pub mod diff;
+pub mod disk;
 pub mod error;
 pub mod export;
 pub mod iter;
+pub mod metric;
 pub mod parse;
+pub mod quantized;
+pub mod search;
 pub mod space;
+pub mod stats;
+pub mod term_map;
 pub mod vector;
@@ -0,0 +1,44 @@
+use crate::vector::Vector;
+
+/// Distance metric between two vectors, see
+/// [`VecSpace::distance`](crate::space::VecSpace::distance).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// One minus the cosine similarity, in `[0, 2]`.
+    Cosine,
+
+    /// L2 distance.
+    Euclidean,
+
+    /// L1 distance.
+    Manhattan,
+}
+
+impl Metric {
+    /// Calculates the distance between `a` and `b`. Both vectors should have the same dimension,
+    /// otherwise only the overlapping components are used.
+    pub fn distance(&self, a: &Vector, b: &Vector) -> f32 {
+        let diffs = a.data().iter().zip(b.data()).map(|(a, b)| a - b);
+        match self {
+            Metric::Cosine => 1.0 - a.cosine(b),
+            Metric::Euclidean => diffs.map(|i| i * i).sum::<f32>().sqrt(),
+            Metric::Manhattan => diffs.map(|i| i.abs()).sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        let a = Vector::new(&[1.0, 0.0], "a");
+        let b = Vector::new(&[4.0, 4.0], "b");
+
+        assert_eq!(Metric::Euclidean.distance(&a, &b), 5.0);
+        assert_eq!(Metric::Manhattan.distance(&a, &b), 7.0);
+        assert!((Metric::Cosine.distance(&a, &b) - (1.0 - a.cosine(&b))).abs() < 1e-6);
+        assert_eq!(Metric::Cosine.distance(&a, &a), 0.0);
+    }
+}
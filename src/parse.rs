@@ -1,11 +1,17 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Read},
+    io::{BufRead, BufReader, Cursor, Read, Write},
     path::Path,
     str,
 };
 
-use crate::{error::Error, space::VecSpace, vector::Vector};
+use crate::{
+    error::Error,
+    export::Exporter,
+    space::VecSpace,
+    vector::{OwnedVector, Vector},
+};
+use ahash::AHashSet;
 
 /// Parser for Word2Vec's .vec files.
 #[derive(Clone, Copy, Debug)]
@@ -15,9 +21,22 @@ pub struct Word2VecParser {
     term_separator: char,
     vec_separator: char,
     binary: bool,
+    length_prefixed_terms: bool,
+    terms_only: bool,
+    #[cfg(feature = "half")]
+    f16: bool,
+    dimension: Option<usize>,
+    skip_lines: usize,
+    comment_prefix: Option<char>,
+    buffer_size: Option<usize>,
+    skip_columns: usize,
 
     // Vec space options
     index_terms: bool,
+    lowercase_terms: bool,
+    warn_duplicates: bool,
+    max_term_len: Option<usize>,
+    skip_long_terms: bool,
 }
 
 impl Word2VecParser {
@@ -32,12 +51,79 @@ impl Word2VecParser {
         self
     }
 
+    /// Parse from binary format where each component is stored as 2 byte half-float. The values
+    /// get converted to `f32`.
+    #[cfg(feature = "half")]
+    pub fn f16_binary(mut self) -> Self {
+        self.binary = true;
+        self.f16 = true;
+        self
+    }
+
+    /// Parse binary files where each term is prefixed with its length in bytes as little endian
+    /// `u16` instead of being terminated by a space. This allows terms containing any character.
+    /// Only used in binary format.
+    pub fn length_prefixed_terms(mut self) -> Self {
+        self.length_prefixed_terms = true;
+        self
+    }
+
+    /// Only parses the terms and skips the vector data. The resulting [`VecSpace`] contains all
+    /// terms with empty vectors and has a dimension of zero. Binary files without header require
+    /// [`Word2VecParser::dimension`] to be set.
+    pub fn terms_only(mut self) -> Self {
+        self.terms_only = true;
+        self
+    }
+
     /// Don't treat the first line as header.
     pub fn no_header(mut self) -> Self {
         self.parse_header = false;
         self
     }
 
+    /// Sets the dimension of the vectors. This is only used if the file has no header. Without a
+    /// header and a given dimension, the dimension of the first vector gets used. This is not
+    /// possible for binary files, which require a dimension if they don't have a header.
+    ///
+    /// In txt files a first line consisting of two integers is only treated as header if its
+    /// dimension matches the given one. Without a given dimension, it has to match the amount of
+    /// components of the next line instead. Files with 1-dimensional vectors whose first line
+    /// could be a header for the following vectors, eg. `2 1`, still need
+    /// [`Word2VecParser::no_header`].
+    pub fn dimension(mut self, dim: usize) -> Self {
+        self.dimension = Some(dim);
+        self
+    }
+
+    /// Skips the first `n` lines of the file. Useful for files with metadata in front of the
+    /// header.
+    pub fn skip_lines(mut self, n: usize) -> Self {
+        self.skip_lines = n;
+        self
+    }
+
+    /// Skips lines starting with `prefix`. In binary files only lines in front of the header are
+    /// skipped, in txt files also lines between vectors.
+    pub fn comment_prefix(mut self, prefix: char) -> Self {
+        self.comment_prefix = Some(prefix);
+        self
+    }
+
+    /// Sets the capacity of the buffer used for reading in bytes. Larger buffers reduce the
+    /// amount of reads for big files. Defaults to the capacity of [`BufReader::new`].
+    pub fn buffer_size(mut self, bytes: usize) -> Self {
+        self.buffer_size = Some(bytes);
+        self
+    }
+
+    /// Ignores the first `n` columns after the term in txt files, eg. an ID column. The dimension
+    /// in the header has to be the amount of the remaining columns.
+    pub fn skip_columns(mut self, n: usize) -> Self {
+        self.skip_columns = n;
+        self
+    }
+
     /// Use a custom term<->Vec separator character.
     pub fn cust_term_separator(mut self, sep: char) -> Self {
         self.term_separator = sep;
@@ -50,6 +136,34 @@ impl Word2VecParser {
         self
     }
 
+    /// Converts all terms to lowercase. If multiple terms are equal after lowercasing, only the
+    /// first one is kept.
+    pub fn lowercase_terms(mut self) -> Self {
+        self.lowercase_terms = true;
+        self
+    }
+
+    /// Tracks terms that occur multiple times while parsing. The duplicates are returned by
+    /// [`Word2VecParser::parse_with_report`].
+    pub fn warn_duplicates(mut self) -> Self {
+        self.warn_duplicates = true;
+        self
+    }
+
+    /// Truncates terms longer than `n` bytes to at most `n` bytes. Terms are only cut at char
+    /// boundaries so truncated terms can be shorter.
+    pub fn max_term_len(mut self, n: usize) -> Self {
+        self.max_term_len = Some(n);
+        self
+    }
+
+    /// Skips vectors with terms longer than [`Word2VecParser::max_term_len`] instead of
+    /// truncating their terms.
+    pub fn skip_long_terms(mut self) -> Self {
+        self.skip_long_terms = true;
+        self
+    }
+
     /// Whether to index the words for faster term->vec lookup.
     pub fn index_terms(mut self, index: bool) -> Self {
         self.index_terms = index;
@@ -57,55 +171,301 @@ impl Word2VecParser {
     }
 
     pub fn parse<R: Read>(&self, reader: R) -> Result<VecSpace, Error> {
-        let mut space = VecSpace::new(0);
+        Ok(self.parse_with_report(reader)?.0)
+    }
+
+    /// Like [`Word2VecParser::parse`] but also returns all terms that occurred multiple times if
+    /// [`Word2VecParser::warn_duplicates`] is enabled. A term is listed once for each of its
+    /// additional occurrences.
+    pub fn parse_with_report<R: Read>(&self, reader: R) -> Result<(VecSpace, Vec<String>), Error> {
+        let mut space = None;
+        let mut duplicates = vec![];
+        self.parse_into(reader, &mut space, &mut duplicates, None)?;
+        let space = space.unwrap_or_else(|| self.new_space(0));
+        Ok((space, duplicates))
+    }
+
+    /// Like [`Word2VecParser::parse`] but calls `map` on each vector before it gets inserted, eg.
+    /// to normalize or round the vectors while parsing. Terms are lowercased and truncated before
+    /// `map` is called. Changing the dimension of a vector results in an [`Error::DimMismatch`].
+    pub fn parse_with_map<R, F>(&self, reader: R, mut map: F) -> Result<VecSpace, Error>
+    where
+        R: Read,
+        F: FnMut(&mut OwnedVector),
+    {
+        let mut space = None;
+        self.parse_into(reader, &mut space, &mut vec![], Some(&mut map))?;
+        Ok(space.unwrap_or_else(|| self.new_space(0)))
+    }
+
+    /// Parses word vectors from in-memory bytes. Useful on platforms without file system access,
+    /// eg. WebAssembly in the browser.
+    #[inline]
+    pub fn parse_bytes(&self, bytes: &[u8]) -> Result<VecSpace, Error> {
+        self.parse(Cursor::new(bytes))
+    }
+
+    /// Parses a word vector file.
+    #[inline]
+    pub fn parse_file<F: AsRef<Path>>(&self, file: F) -> Result<VecSpace, Error> {
+        self.parse(File::open(file)?)
+    }
+
+    /// Parses multiple word vector files into a single [`VecSpace`]. All files must have the same
+    /// dimension, otherwise an [`Error::DimMismatch`] is returned.
+    pub fn parse_files<I, P>(&self, files: I) -> Result<VecSpace, Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut space = None;
+        for file in files {
+            self.parse_into(File::open(file)?, &mut space, &mut vec![], None)?;
+        }
+        Ok(space.unwrap_or_else(|| self.new_space(0)))
+    }
 
-        let mut parsed_header = false;
+    /// Parses all vectors of `reader` and appends them to `space`. If `space` is `None`, a new
+    /// space gets created as soon as the dimension is known. Duplicate terms get added to
+    /// `duplicates` if enabled. If set, `map` gets called on each vector before inserting it.
+    fn parse_into<R: Read>(
+        &self,
+        reader: R,
+        space: &mut Option<VecSpace>,
+        duplicates: &mut Vec<String>,
+        mut map: Option<&mut dyn FnMut(&mut OwnedVector)>,
+    ) -> Result<(), Error> {
         let mut line_buf = vec![];
         let mut float_buf = vec![];
 
-        let mut r = BufReader::new(reader);
+        let mut r = self.buf_reader(reader);
 
-        loop {
-            line_buf.clear();
+        let (header, mut header_is_vec) = self.read_header(&mut r, &mut line_buf)?;
+        let (count, dim) = match header {
+            Some((count, dim)) => (Some(count), Some(dim)),
+            // Without parsing vectors, the space doesn't know the dimension of the file.
+            None if self.binary
+                && self.dimension.is_none()
+                && (space.is_none() || self.terms_only) =>
+            {
+                return Err(Error::MissingDimension);
+            }
+            None => (None, self.dimension),
+        };
 
-            if !parsed_header {
-                if r.read_until(b'\n', &mut line_buf).unwrap() == 0 {
-                    return Err(Error::InvalidVectorFormat)?;
+        if let Some(dim) = dim {
+            let space_dim = if self.terms_only { 0 } else { dim };
+            match space {
+                Some(space) if space.dim() != space_dim => {
+                    return Err(Error::DimMismatch(dim, space.dim()));
                 }
+                Some(_) => (),
+                None => *space = Some(self.new_space(space_dim)),
+            }
+            float_buf.reserve_exact(space_dim);
+        }
+
+        if let (Some(count), Some(space)) = (count, space.as_mut()) {
+            space.add_declared_len(count);
+        }
+
+        // Terms of all vectors in the space, to detect duplicates.
+        let track_terms = self.lowercase_terms || self.warn_duplicates;
+        let mut seen: AHashSet<String> = AHashSet::new();
+        if track_terms {
+            seen.extend(space.iter().flat_map(|i| i.terms()).cloned());
+        }
 
-                let (_, dim) = self.parse_header(&line_buf)?;
-                space = VecSpace::new(dim);
-                float_buf.reserve_exact(dim);
+        loop {
+            let vec_len = dim.or_else(|| space.as_ref().map(|i| i.dim())).unwrap_or(0);
 
-                if self.index_terms {
-                    space = space.with_termmap();
+            // Parse line and insert into space
+            let vec = self.next_vec(
+                &mut r,
+                &mut float_buf,
+                &mut line_buf,
+                vec_len,
+                &mut header_is_vec,
+            );
+            if vec == Err(Error::EOF) {
+                break;
+            }
+            let vec = vec?;
+            let space = space.get_or_insert_with(|| self.new_space(vec.dim()));
+
+            let mut term = vec.term();
+            if let Some(max_len) = self.max_term_len.filter(|i| term.len() > *i) {
+                if self.skip_long_terms {
+                    continue;
                 }
+                term = truncate_term(term, max_len);
+            }
 
-                parsed_header = true;
+            if !track_terms {
+                insert_mapped(space, Vector::new(vec.data(), term), &mut map)?;
+                continue;
+            }
+
+            let term = if self.lowercase_terms {
+                term.to_lowercase()
+            } else {
+                term.to_string()
+            };
 
-                // Don't parse header as vector
+            let is_duplicate = seen.contains(&term);
+            if is_duplicate && self.warn_duplicates {
+                duplicates.push(term.clone());
+            }
+
+            // Only keep the first vector for terms that collide after lowercasing.
+            if is_duplicate && self.lowercase_terms {
                 continue;
             }
 
-            // Parse line and insert into space
-            let vec = self.parse_vec(&mut r, &mut float_buf, &mut line_buf, space.dim());
-            if vec == Err(Error::EOF) {
+            insert_mapped(space, Vector::new(vec.data(), &term), &mut map)?;
+            if !is_duplicate {
+                seen.insert(term);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skips the configured amount of lines and reads the header. Returns the vector count and
+    /// dimension of the header if there is one.
+    ///
+    /// If a header is expected but the first line turned out to be a vector, `true` is returned
+    /// as well and the line is kept in `line_buf`. See [`Word2VecParser::dimension`] for how
+    /// ambiguous txt lines are handled.
+    pub(crate) fn read_header<R: BufRead>(
+        &self,
+        r: &mut R,
+        line_buf: &mut Vec<u8>,
+    ) -> Result<(Option<(usize, usize)>, bool), Error> {
+        for _ in 0..self.skip_lines {
+            if r.read_until(b'\n', line_buf)? == 0 {
                 break;
             }
-            space.insert(&vec?)?;
+        }
+        line_buf.clear();
+
+        if !self.parse_header {
+            return Ok((None, false));
+        }
+
+        if !self.read_line(r, line_buf)? {
+            return Err(Error::InvalidVectorFormat);
+        }
+
+        if !self.binary {
+            let line = str::from_utf8(line_buf)?;
+            if !is_txt_header(line) || !self.header_dim_matches(r, line)? {
+                return Ok((None, true));
+            }
         }
 
-        Ok(space)
+        Ok((Some(self.parse_header(line_buf)?), false))
     }
 
-    /// Parses a word vector file.
-    #[inline]
-    pub fn parse_file<F: AsRef<Path>>(&self, file: F) -> Result<VecSpace, Error> {
-        self.parse(File::open(file)?)
+    /// Parses the next vector. If `pending_line` is set, the vector is parsed from the line
+    /// already in `line_buf` instead of reading a new one.
+    fn next_vec<'v, 't, R: BufRead>(
+        &self,
+        r: &mut R,
+        vbuf: &'v mut Vec<f32>,
+        line_buf: &'t mut Vec<u8>,
+        vec_len: usize,
+        pending_line: &mut bool,
+    ) -> Result<Vector<'v, 't>, Error> {
+        if !*pending_line {
+            return self.parse_vec(r, vbuf, line_buf, vec_len);
+        }
+
+        *pending_line = false;
+        vbuf.clear();
+        self.parse_vec_txt(str::from_utf8(line_buf)?, vbuf, vec_len)
+    }
+
+    /// Wraps `reader` into a [`BufReader`] with the configured buffer size.
+    fn buf_reader<R: Read>(&self, reader: R) -> BufReader<R> {
+        match self.buffer_size {
+            Some(size) => BufReader::with_capacity(size, reader),
+            None => BufReader::new(reader),
+        }
+    }
+
+    /// Creates a new empty [`VecSpace`] with the parsers space options.
+    fn new_space(&self, dim: usize) -> VecSpace {
+        let space = VecSpace::new(dim);
+        if self.index_terms {
+            return space.with_termmap();
+        }
+        space
+    }
+
+    /// Reads the next line which is not a comment into `buf`. Returns `false` if the end of the
+    /// reader has been reached.
+    fn read_line<R: BufRead>(&self, r: &mut R, buf: &mut Vec<u8>) -> Result<bool, Error> {
+        loop {
+            buf.clear();
+            if r.read_until(b'\n', buf)? == 0 {
+                return Ok(false);
+            }
+
+            if !self.is_comment(buf) {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Returns `false` if the dimension of the txt header `line` doesn't match the configured
+    /// dimension or, without one, the amount of components of the next line. The next line is
+    /// only peeked at and not consumed. If it can't be checked, the header is assumed to be valid.
+    fn header_dim_matches<R: BufRead>(&self, r: &mut R, line: &str) -> Result<bool, Error> {
+        let dim = line.split_whitespace().nth(1).map(|i| i.parse::<usize>());
+        let Some(Ok(dim)) = dim else {
+            return Ok(true);
+        };
+
+        if let Some(exp) = self.dimension {
+            return Ok(dim == exp);
+        }
+
+        let buf = r.fill_buf()?;
+        let (next, complete) = match buf.iter().position(|i| *i == b'\n') {
+            Some(end) => (&buf[..end], true),
+            None => (buf, false),
+        };
+        if next.is_empty() || self.is_comment(next) {
+            return Ok(true);
+        }
+
+        let next = String::from_utf8_lossy(next);
+        let next = next.trim_end_matches('\r');
+        let Some(split) = next.find(self.term_separator) else {
+            return Ok(true);
+        };
+
+        let count = self
+            .components(&next[split + self.term_separator.len_utf8()..])
+            .count();
+
+        // A line which doesn't fit into the buffer can have more components than visible.
+        Ok(if complete { count == dim } else { count <= dim })
+    }
+
+    /// Returns `true` if the given line is a comment.
+    fn is_comment(&self, line: &[u8]) -> bool {
+        let Some(prefix) = self.comment_prefix else {
+            return false;
+        };
+
+        let mut buf = [0u8; 4];
+        line.starts_with(prefix.encode_utf8(&mut buf).as_bytes())
     }
 
     /// Parses a single vec line
-    fn parse_vec<'v, 't, R: BufRead>(
+    pub(crate) fn parse_vec<'v, 't, R: BufRead>(
         &self,
         r: &mut R,
         vbuf: &'v mut Vec<f32>,
@@ -118,36 +478,62 @@ impl Word2VecParser {
         if self.binary {
             self.parse_vec_bin(r, vbuf, line_buf, vec_len)
         } else {
-            if r.read_until(b'\n', line_buf)? == 0 {
+            if !self.read_line(r, line_buf)? {
                 return Err(Error::EOF);
             }
             let line = str::from_utf8(line_buf)?;
-            self.parse_vec_txt(line, vbuf)
+            self.parse_vec_txt(line, vbuf, vec_len)
         }
     }
 
     /// Parses a word vector from txt format.
+    ///
+    /// If `vec_len` isn't zero, lines with a different amount of floats result in an
+    /// [`Error::DimMismatch`] with the amount of floats in the line.
     fn parse_vec_txt<'v, 't>(
         &self,
         line: &'t str,
         buf: &'v mut Vec<f32>,
+        vec_len: usize,
     ) -> Result<Vector<'v, 't>, Error> {
+        // Files created on Windows end lines with `\r\n`.
+        let line = line.trim_end_matches(['\r', '\n']);
         let term_vec_split = line
             .find(self.term_separator)
             .ok_or(Error::InvalidVectorFormat)?;
+        let term = &line[..term_vec_split];
 
-        for i in line[term_vec_split + 1..]
-            .trim()
-            .split(self.vec_separator)
+        if self.terms_only {
+            return Ok(Vector::new(buf, term));
+        }
+
+        for i in self
+            .components(&line[term_vec_split + self.term_separator.len_utf8()..])
             .map(|i| i.parse::<f32>())
         {
             buf.push(i.map_err(fmt_err)?);
         }
 
-        let term = &line[..term_vec_split];
+        if vec_len != 0 && buf.len() != vec_len {
+            return Err(Error::DimMismatch(buf.len(), vec_len));
+        }
+
         Ok(Vector::new(buf, &term))
     }
 
+    /// Splits the part of a txt line following the term into its components, skipping the
+    /// configured amount of columns.
+    fn components<'a>(&self, values: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        // Empty tokens are caused by repeated separators and can be ignored. Whitespace around
+        // tokens is allowed for separators other than space, eg. `1.0, 2.0`.
+        values
+            .trim()
+            .split(self.vec_separator)
+            .map(|i| i.trim())
+            .filter(|i| !i.is_empty())
+            .skip(self.skip_columns)
+    }
+
     /// Parses a word vector from bin format.
     fn parse_vec_bin<'v, 't, R: BufRead>(
         &self,
@@ -156,11 +542,56 @@ impl Word2VecParser {
         rbuf: &'t mut Vec<u8>,
         vec_len: usize,
     ) -> Result<Vector<'v, 't>, Error> {
-        if r.read_until(b' ', rbuf)? == 0 {
-            return Err(Error::EOF);
+        if self.length_prefixed_terms {
+            if r.fill_buf()?.is_empty() {
+                return Err(Error::EOF);
+            }
+
+            let mut len_buf = [0u8; 2];
+            r.read_exact(&mut len_buf)?;
+            rbuf.resize(u16::from_le_bytes(len_buf) as usize, 0);
+            r.read_exact(rbuf)?;
+        } else {
+            if r.read_until(b' ', rbuf)? == 0 {
+                return Err(Error::EOF);
+            }
+
+            // The original word2vec tool writes a '\n' after each vector.
+            if rbuf[0] == b'\n' {
+                rbuf.remove(0);
+                if rbuf.is_empty() {
+                    return Err(Error::EOF);
+                }
+            }
+
+            // Remove the separating space.
+            rbuf.pop();
+        }
+
+        let term = str::from_utf8(rbuf)?;
+
+        if self.terms_only {
+            #[cfg(feature = "half")]
+            let component_size = if self.f16 { 2 } else { 4 };
+            #[cfg(not(feature = "half"))]
+            let component_size = 4;
+
+            let len = (vec_len * component_size) as u64;
+            if std::io::copy(&mut r.take(len), &mut std::io::sink())? != len {
+                return Err(Error::InvalidVectorFormat);
+            }
+            return Ok(Vector::new(vbuf, term));
         }
 
-        let term = str::from_utf8(&rbuf[..rbuf.len() - 1])?;
+        #[cfg(feature = "half")]
+        if self.f16 {
+            let mut half_buf = [0u8; 2];
+            for _ in 0..vec_len {
+                r.read_exact(&mut half_buf)?;
+                vbuf.push(half::f16::from_le_bytes(half_buf).to_f32());
+            }
+            return Ok(Vector::new(vbuf, term));
+        }
 
         let mut float_buf = [0u8; 4];
         for _ in 0..vec_len {
@@ -190,10 +621,10 @@ impl Word2VecParser {
             .0;
 
         let count = str::from_utf8(&line[..space])?;
-        let len = str::from_utf8(&line[space + 1..line.len() - 1])?;
+        let len = str::from_utf8(&line[space + 1..])?.trim_end_matches(['\r', '\n']);
 
-        let count: usize = count.parse().unwrap();
-        let len: usize = len.parse().unwrap();
+        let count: usize = count.parse().map_err(fmt_err)?;
+        let len: usize = len.parse().map_err(fmt_err)?;
 
         Ok((count, len))
     }
@@ -212,6 +643,97 @@ impl Word2VecParser {
     }
 }
 
+/// Converts the vectors from `reader` into another format by streaming them one by one from
+/// `parser` into an [`Exporter`], without keeping all vectors in memory. The vectors get exported
+/// in binary format if `exporter_binary` is set. Returns the amount of bytes written.
+///
+/// The header of the input gets copied. If the input has no header, none is written either.
+/// Options affecting the [`VecSpace`] like [`Word2VecParser::lowercase_terms`] are not applied.
+pub fn convert<R: Read, W: Write>(
+    parser: &Word2VecParser,
+    reader: R,
+    exporter_binary: bool,
+    writer: W,
+) -> Result<usize, Error> {
+    let mut line_buf = vec![];
+    let mut float_buf = vec![];
+    let mut r = parser.buf_reader(reader);
+
+    let mut exporter = Exporter::new(writer);
+    if exporter_binary {
+        exporter = exporter.use_binary();
+    }
+
+    let (header, mut header_is_vec) = parser.read_header(&mut r, &mut line_buf)?;
+    let (mut dim, mut n) = match header {
+        Some((count, dim)) => (Some(dim), exporter.write_space_header(count, dim, 0)?),
+        None => {
+            exporter = exporter.no_header();
+            (parser.dimension, 0)
+        }
+    };
+
+    if parser.binary && dim.is_none() {
+        return Err(Error::MissingDimension);
+    }
+
+    loop {
+        let vec = parser.next_vec(
+            &mut r,
+            &mut float_buf,
+            &mut line_buf,
+            dim.unwrap_or(0),
+            &mut header_is_vec,
+        );
+        if vec == Err(Error::EOF) {
+            break;
+        }
+        let vec = vec?;
+
+        let dim = *dim.get_or_insert(vec.dim());
+        if vec.dim() != dim {
+            return Err(Error::DimMismatch(vec.dim(), dim));
+        }
+
+        n += exporter.export_vectors([vec])?;
+    }
+
+    Ok(n)
+}
+
+/// Returns `true` if `line` looks like a txt header, consisting of the vector count and the
+/// dimension. Vector lines have more tokens or a term which is not a number.
+fn is_txt_header(line: &str) -> bool {
+    let mut split = line.split_whitespace();
+    let count_ok = split.next().is_some_and(|i| i.parse::<usize>().is_ok());
+    count_ok && split.count() <= 1
+}
+
+/// Inserts `vec` into `space` after passing it to `map` if set.
+#[inline]
+fn insert_mapped(
+    space: &mut VecSpace,
+    vec: Vector,
+    map: &mut Option<&mut dyn FnMut(&mut OwnedVector)>,
+) -> Result<(), Error> {
+    let Some(map) = map else {
+        return space.insert(vec);
+    };
+
+    let mut vec = borrowme::ToOwned::to_owned(&vec);
+    map(&mut vec);
+    space.insert(&vec)
+}
+
+/// Cuts `term` to at most `max_len` bytes at the last char boundary.
+fn truncate_term(term: &str, max_len: usize) -> &str {
+    let mut end = max_len.min(term.len());
+    while !term.is_char_boundary(end) {
+        end -= 1;
+    }
+    &term[..end]
+}
+
 #[inline]
 fn fmt_err<T>(_: T) -> Error {
     Error::InvalidVectorFormat
@@ -224,7 +746,459 @@ impl Default for Word2VecParser {
             term_separator: ' ',
             vec_separator: ' ',
             index_terms: false,
+            lowercase_terms: false,
+            warn_duplicates: false,
             binary: false,
+            length_prefixed_terms: false,
+            terms_only: false,
+            #[cfg(feature = "half")]
+            f16: false,
+            dimension: None,
+            skip_lines: 0,
+            comment_prefix: None,
+            buffer_size: None,
+            skip_columns: 0,
+            max_term_len: None,
+            skip_long_terms: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_convert() {
+        let data = "3 3\na 1.0 2.0 3.0\nb 0.5 0.1 0.2\nc 7.0 8.0 9.0\n";
+        let parser = Word2VecParser::new();
+
+        let mut buf = vec![];
+        let n = convert(&parser, Cursor::new(data), true, &mut buf).unwrap();
+        assert_eq!(n, buf.len());
+
+        let space = parser.parse(Cursor::new(data)).unwrap();
+        let converted = parser.binary().parse(Cursor::new(&buf)).unwrap();
+        assert_eq!(converted, space);
+
+        // Without header
+        let data = "a 1.0 2.0 3.0\nb 0.5 0.1\n";
+        let parser = Word2VecParser::new().no_header();
+        let res = convert(&parser, Cursor::new(data), false, vec![]);
+        assert_eq!(res, Err(Error::DimMismatch(2, 3)));
+    }
+
+    #[test]
+    fn test_buffer_size() {
+        let data = "2 3\na 1.0 2.0 3.0\nb 0.5 0.1 0.2\n";
+        let exp = Word2VecParser::new().parse(Cursor::new(data)).unwrap();
+
+        for size in [4, 1 << 20] {
+            let parser = Word2VecParser::new().buffer_size(size);
+            assert_eq!(parser.parse(Cursor::new(data)).unwrap(), exp);
+
+            let mut buf = vec![];
+            convert(&parser, Cursor::new(data), true, &mut buf).unwrap();
+            let parsed = parser.binary().parse(Cursor::new(&buf)).unwrap();
+            assert_eq!(parsed, exp);
+        }
+    }
+
+    #[test]
+    fn test_parse_extra_whitespace() {
+        let data = "2 3\na 1.0  2.0 3.0 \nb 0.5 0.1   0.2  \n";
+
+        let space = Word2VecParser::new().parse(Cursor::new(data)).unwrap();
+        assert_eq!(space.dim(), 3);
+        assert_eq!(space.get(0), Some(Vector::new(&[1.0, 2.0, 3.0], "a")));
+        assert_eq!(space.get(1), Some(Vector::new(&[0.5, 0.1, 0.2], "b")));
+    }
+
+    #[test]
+    fn test_parse_terms_only() {
+        let data = "2 3\na 1.0 2.0 3.0\nb 0.5 0.1 0.2\n";
+        let space = Word2VecParser::new()
+            .terms_only()
+            .index_terms(true)
+            .parse(Cursor::new(data))
+            .unwrap();
+
+        assert_eq!(space.dim(), 0);
+        assert_eq!(space.len(), 2);
+        assert_eq!(space.words(), ["a", "b"]);
+        assert_eq!(space.find_term("b"), Some(Vector::new(&[], "b")));
+
+        let mut buf = vec![];
+        convert(&Word2VecParser::new(), Cursor::new(data), true, &mut buf).unwrap();
+        let space = Word2VecParser::new()
+            .binary()
+            .terms_only()
+            .parse(Cursor::new(&buf))
+            .unwrap();
+        assert_eq!(space.dim(), 0);
+        assert_eq!(space.words(), ["a", "b"]);
+        assert!(space.get(0).unwrap().data().is_empty());
+    }
+
+    #[test]
+    fn test_parse_custom_separators() {
+        let parser = Word2VecParser::new()
+            .cust_term_separator('\t')
+            .cust_vec_separator(',');
+
+        let mut exp = VecSpace::new(3);
+        exp.extend([
+            Vector::new(&[1.0, 2.0, 3.0], "a b"),
+            Vector::new(&[4.0, 5.0, 6.0], "c"),
+        ]);
+
+        for data in [
+            "2 3\na b\t1.0,2.0,3.0\nc\t4.0,5.0,6.0\n",
+            "2 3\na b\t1.0,2.0,3.0\nc\t4.0,5.0,6.0",
+            "2 3\na b\t1.0, 2.0, 3.0\nc\t4.0,5.0,6.0,\n",
+        ] {
+            let space = parser.parse(Cursor::new(data)).unwrap();
+            assert_eq!(space, exp);
         }
+
+        let space = Word2VecParser::new()
+            .cust_term_separator('\u{3000}')
+            .parse(Cursor::new(
+                "2 3\na b\u{3000}1.0 2.0 3.0\nc\u{3000}4.0 5.0 6.0\n",
+            ))
+            .unwrap();
+        assert_eq!(space, exp);
+    }
+
+    #[test]
+    fn test_parse_with_map() {
+        let data = "2 3\na 1.0 2.0 3.0\nb 4.0 5.0 6.0\n";
+        let mut calls = 0;
+        let space = Word2VecParser::new()
+            .index_terms(true)
+            .parse_with_map(Cursor::new(data), |v| {
+                calls += 1;
+                *v = OwnedVector::from_iter_with_term(
+                    v.term().to_string(),
+                    v.data().iter().map(|i| i * 2.0),
+                );
+            })
+            .unwrap();
+
+        assert_eq!(calls, 2);
+        assert_eq!(space.get(0), Some(Vector::new(&[2.0, 4.0, 6.0], "a")));
+        assert_eq!(
+            space.find_term("b"),
+            Some(Vector::new(&[8.0, 10.0, 12.0], "b"))
+        );
+
+        let res = Word2VecParser::new().parse_with_map(Cursor::new(data), |v| {
+            *v = v.padded(4);
+        });
+        assert_eq!(res, Err(Error::DimMismatch(4, 3)));
+    }
+
+    #[test]
+    fn test_parse_txt_dim_check() {
+        let data = "3 3\na 1.0 2.0 3.0\nb 4.0 5.0\nc 7.0 8.0 9.0\n";
+        let res = Word2VecParser::new().parse(Cursor::new(data));
+        assert_eq!(res, Err(Error::DimMismatch(2, 3)));
+
+        // The dimension of the first vector is used without header.
+        let res = Word2VecParser::new()
+            .no_header()
+            .parse(Cursor::new("a 1.0 2.0 3.0\nb 4.0 5.0 6.0 7.0\n"));
+        assert_eq!(res, Err(Error::DimMismatch(4, 3)));
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let data: &[u8] = b"2 3\na 1.0 2.0 3.0\nb 4.0 5.0 6.0\n";
+        let space = Word2VecParser::new().parse_bytes(data).unwrap();
+        assert_eq!(
+            space,
+            Word2VecParser::new().parse(Cursor::new(data)).unwrap()
+        );
+        assert_eq!(space.len(), 2);
+        assert_eq!(space.get(1), Some(Vector::new(&[4.0, 5.0, 6.0], "b")));
+    }
+
+    #[test]
+    fn test_parse_max_term_len() {
+        let long = "x".repeat(1000);
+        let data = format!("3 2\na 1.0 2.0\n{long} 3.0 4.0\nüüü 5.0 6.0\n");
+
+        let space = Word2VecParser::new()
+            .max_term_len(5)
+            .index_terms(true)
+            .parse(Cursor::new(&data))
+            .unwrap();
+        assert_eq!(space.len(), 3);
+        assert_eq!(space.get(1), Some(Vector::new(&[3.0, 4.0], "xxxxx")));
+        assert_eq!(space.get(2).unwrap().term(), "üü");
+        assert!(space.find_term("xxxxx").is_some());
+
+        let space = Word2VecParser::new()
+            .max_term_len(5)
+            .skip_long_terms()
+            .parse(Cursor::new(&data))
+            .unwrap();
+        assert_eq!(space.len(), 1);
+        assert_eq!(space.get(0).unwrap().term(), "a");
+    }
+
+    #[test]
+    fn test_parse_crlf() {
+        let data = "2 3\r\na 1.0 2.0 3.0\r\nb 4.0 5.0 6.0\r\n";
+        let space = Word2VecParser::new().parse(Cursor::new(data)).unwrap();
+
+        assert_eq!(space.len(), 2);
+        assert_eq!(space.dim(), 3);
+        assert_eq!(space.get(0), Some(Vector::new(&[1.0, 2.0, 3.0], "a")));
+        assert_eq!(space.get(1), Some(Vector::new(&[4.0, 5.0, 6.0], "b")));
+
+        let space = Word2VecParser::new()
+            .cust_term_separator('\t')
+            .cust_vec_separator(',')
+            .parse(Cursor::new("1 2\r\na\t1.0,2.0\r\n"))
+            .unwrap();
+        assert_eq!(space.get(0), Some(Vector::new(&[1.0, 2.0], "a")));
+    }
+
+    #[test]
+    fn test_parse_skip_columns() {
+        let data = "2 3\na 17 1.0 2.0 3.0\nb 18 4.0 5.0 6.0\n";
+
+        let space = Word2VecParser::new()
+            .skip_columns(1)
+            .parse(Cursor::new(data))
+            .unwrap();
+
+        assert_eq!(space.dim(), 3);
+        assert_eq!(space.get(0), Some(Vector::new(&[1.0, 2.0, 3.0], "a")));
+        assert_eq!(space.get(1), Some(Vector::new(&[4.0, 5.0, 6.0], "b")));
+
+        let res = Word2VecParser::new().parse(Cursor::new(data));
+        assert_eq!(res, Err(Error::DimMismatch(4, 3)));
+    }
+
+    #[test]
+    fn test_parse_missing_header() {
+        let data = "a 1.0 2.0 3.0\nb 0.5 0.1 0.2\n";
+
+        let space = Word2VecParser::new()
+            .index_terms(true)
+            .parse(Cursor::new(data))
+            .unwrap();
+
+        assert_eq!(space.dim(), 3);
+        assert_eq!(space.len(), 2);
+        assert_eq!(space.declared_len(), None);
+        assert_eq!(
+            space.find_term("a"),
+            Some(Vector::new(&[1.0, 2.0, 3.0], "a"))
+        );
+
+        // A numeric term is not mistaken for a header.
+        let data = "1 1.0 2.0 3.0\nb 0.5 0.1 0.2\n";
+        let space = Word2VecParser::new().parse(Cursor::new(data)).unwrap();
+        assert_eq!(space.len(), 2);
+        assert_eq!(space.get(0), Some(Vector::new(&[1.0, 2.0, 3.0], "1")));
+
+        // A 1-dimensional vector with a numeric term is only a header if the next line fits.
+        let data = "42 7\nb 0.5\n";
+        for parser in [Word2VecParser::new(), Word2VecParser::new().dimension(1)] {
+            let space = parser.parse(Cursor::new(data)).unwrap();
+            assert_eq!(space.len(), 2);
+            assert_eq!(space.get(0), Some(Vector::new(&[7.0], "42")));
+            assert_eq!(space.get(1), Some(Vector::new(&[0.5], "b")));
+        }
+
+        let data = "1 1\nb 0.5\n";
+        let space = Word2VecParser::new().parse(Cursor::new(data)).unwrap();
+        assert_eq!(space.declared_len(), Some(1));
+        assert_eq!(space.get(0), Some(Vector::new(&[0.5], "b")));
+    }
+
+    #[test]
+    fn test_parse_no_header_dim() {
+        let data = "a 1.0 2.0 3.0 4.0\nb 0.5 0.1 0.2 0.3\n";
+
+        let space = Word2VecParser::new()
+            .dimension(4)
+            .no_header()
+            .index_terms(true)
+            .parse(Cursor::new(data))
+            .unwrap();
+
+        assert_eq!(space.dim(), 4);
+        assert_eq!(space.len(), 2);
+        assert_eq!(
+            space.find_term("a").unwrap(),
+            Vector::new(&[1.0, 2.0, 3.0, 4.0], "a")
+        );
+        assert_eq!(
+            space.find_term("b").unwrap(),
+            Vector::new(&[0.5, 0.1, 0.2, 0.3], "b")
+        );
+
+        let res = Word2VecParser::new()
+            .dimension(3)
+            .no_header()
+            .parse(Cursor::new(data));
+        assert_eq!(res, Err(Error::DimMismatch(4, 3)));
+    }
+
+    #[test]
+    fn test_parse_skip_comments() {
+        let data = "# some metadata\n# more metadata\n2 3\na 1.0 2.0 3.0\nb 4.0 5.0 6.0\n";
+
+        let mut exp = VecSpace::new(3);
+        exp.extend([
+            Vector::new(&[1.0, 2.0, 3.0], "a"),
+            Vector::new(&[4.0, 5.0, 6.0], "b"),
+        ]);
+
+        let space = Word2VecParser::new()
+            .comment_prefix('#')
+            .parse(Cursor::new(data))
+            .unwrap();
+        assert_eq!(space, exp);
+
+        let space = Word2VecParser::new()
+            .skip_lines(2)
+            .parse(Cursor::new(data))
+            .unwrap();
+        assert_eq!(space, exp);
+
+        let res = Word2VecParser::new().parse(Cursor::new(data));
+        assert_eq!(res, Err(Error::InvalidVectorFormat));
+
+        let data = "2 3\na 1.0 2.0 3.0\n# metadata\nb 4.0 5.0 6.0\n#\n";
+        let space = Word2VecParser::new()
+            .comment_prefix('#')
+            .parse(Cursor::new(data))
+            .unwrap();
+        assert_eq!(space, exp);
+    }
+
+    #[test]
+    fn test_parse_bin_invalid_header() {
+        let parser = Word2VecParser::new().binary();
+        for header in [&b"abc 3\n"[..], b"3 \n", b"3 x\n"] {
+            let res = parser.parse(Cursor::new(header));
+            assert_eq!(res, Err(Error::InvalidVectorFormat));
+        }
+    }
+
+    #[test]
+    fn test_parse_bin_no_header() {
+        let vecs = [
+            Vector::new(&[1.0, 2.0, 3.0], "a"),
+            Vector::new(&[4.0, 5.0, 6.0], "b"),
+        ];
+
+        let mut data = vec![];
+        for vec in vecs {
+            data.extend_from_slice(vec.term().as_bytes());
+            data.push(b' ');
+            for v in vec.data() {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+
+        let parser = Word2VecParser::new().binary().no_header();
+
+        let res = parser.parse(Cursor::new(&data));
+        assert_eq!(res, Err(Error::MissingDimension));
+
+        let space = parser.dimension(3).parse(Cursor::new(&data)).unwrap();
+        let mut exp = VecSpace::new(3);
+        exp.extend(vecs);
+        assert_eq!(space, exp);
+    }
+
+    #[test]
+    fn test_parse_files() {
+        // Unique names so concurrent test runs don't interfere with each other.
+        let dir = std::env::temp_dir();
+        let pid = std::process::id();
+        let file1 = dir.join(format!("word_vec_parse_files_{pid}_1.vec"));
+        let file2 = dir.join(format!("word_vec_parse_files_{pid}_2.vec"));
+        let file3 = dir.join(format!("word_vec_parse_files_{pid}_3.vec"));
+        std::fs::write(&file1, "2 3\na 1.0 2.0 3.0\nb 4.0 5.0 6.0\n").unwrap();
+        std::fs::write(&file2, "1 3\nc 7.0 8.0 9.0\n").unwrap();
+        std::fs::write(&file3, "1 2\nd 7.0 8.0\n").unwrap();
+
+        let space = Word2VecParser::new()
+            .index_terms(true)
+            .parse_files([&file1, &file2]);
+        let mismatch = Word2VecParser::new().parse_files([&file1, &file3]);
+
+        // Remove the files before asserting so they don't stay around on failure.
+        for file in [file1, file2, file3] {
+            std::fs::remove_file(file).unwrap();
+        }
+
+        let space = space.unwrap();
+        assert_eq!(space.len(), 3);
+        assert_eq!(space.dim(), 3);
+        assert_eq!(
+            space.find_term("c").unwrap(),
+            Vector::new(&[7.0, 8.0, 9.0], "c")
+        );
+
+        assert_eq!(mismatch, Err(Error::DimMismatch(2, 3)));
+    }
+
+    #[test]
+    fn test_declared_len() {
+        // Truncated file which declares more vectors than it contains.
+        let data = "5 3\na 1.0 2.0 3.0\nb 4.0 5.0 6.0\n";
+        let space = Word2VecParser::new().parse(Cursor::new(data)).unwrap();
+        assert_eq!(space.declared_len(), Some(5));
+        assert_eq!(space.len(), 2);
+
+        let data = "a 1.0 2.0 3.0\nb 4.0 5.0 6.0\n";
+        let space = Word2VecParser::new()
+            .no_header()
+            .parse(Cursor::new(data))
+            .unwrap();
+        assert_eq!(space.declared_len(), None);
+        assert_eq!(VecSpace::new(3).declared_len(), None);
+    }
+
+    #[test]
+    fn test_lowercase_terms() {
+        let data = "4 2\nHello 1.0 2.0\nWorld 3.0 4.0\nhello 5.0 6.0\nHELLO 7.0 8.0\n";
+
+        let space = Word2VecParser::new()
+            .lowercase_terms()
+            .index_terms(true)
+            .parse(Cursor::new(data))
+            .unwrap();
+
+        assert_eq!(space.len(), 2);
+        assert!(space.terms().eq(["hello", "world"]));
+        assert_eq!(space.find_term("hello").unwrap().data(), &[1.0, 2.0]);
+        assert_eq!(space.find_term("Hello"), None);
+    }
+
+    #[test]
+    fn test_warn_duplicates() {
+        let data = "3 3\nterm1 1.2 2.0 4.4\nterm3 2.3 1.0 3.4\nterm3 3.1 9.4 3.0\n";
+
+        let (space, duplicates) = Word2VecParser::new()
+            .warn_duplicates()
+            .parse_with_report(Cursor::new(data))
+            .unwrap();
+        assert_eq!(space.len(), 3);
+        assert_eq!(duplicates, ["term3"]);
+
+        let (_, duplicates) = Word2VecParser::new()
+            .parse_with_report(Cursor::new(data))
+            .unwrap();
+        assert!(duplicates.is_empty());
     }
 }
@@ -0,0 +1,248 @@
+use crate::{as_vector::AsVectorRef, space::VecSpace};
+use order_struct::{float_ord::FloatOrd, OrderVal};
+
+/// A read-only copy of a [`VecSpace`] with all components quantized to `i8`, using a quarter of
+/// the memory of the original space.
+///
+/// All components share a single scale, calculated from the largest absolute component of the
+/// space. Similarities calculated on the quantized vectors are approximations and small
+/// differences between scores may be lost, so the order of close results can differ from the
+/// order in the original space.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantizedVecSpace {
+    data: Vec<i8>,
+    words: Vec<String>,
+    norms: Vec<f32>,
+    dimension: usize,
+    scale: f32,
+}
+
+/// A query vector quantized with the scale of a [`QuantizedVecSpace`]. Created by
+/// [`QuantizedVecSpace::quantize_query`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantizedQuery {
+    data: Vec<i8>,
+    norm: f32,
+}
+
+impl QuantizedVecSpace {
+    /// Quantizes all vectors of `space`.
+    ///
+    /// The scale is derived from the largest finite absolute component of all vectors, so a
+    /// single outlier component makes the quantization steps coarse for all dimensions and
+    /// collapses small components to `0`. Clip outliers first, eg. using
+    /// [`VecSpace::clamp_all`], to keep more precision. NaN components are quantized to `0` and
+    /// infinite components to the largest step.
+    pub fn new(space: &VecSpace) -> Self {
+        let max = space
+            .iter()
+            .map(|v| {
+                v.data()
+                    .iter()
+                    .filter(|i| i.is_finite())
+                    .fold(0.0f32, |m, i| m.max(i.abs()))
+            })
+            .fold(0.0f32, f32::max);
+        let scale = if max > 0.0 { max / i8::MAX as f32 } else { 1.0 };
+
+        let mut data = Vec::with_capacity(space.len() * space.dim());
+        for v in space.iter() {
+            data.extend(v.data().iter().map(|i| quantize(*i, scale)));
+        }
+
+        let dim = space.dim();
+        let norms = (0..space.len())
+            .map(|i| norm(&data[i * dim..(i + 1) * dim]))
+            .collect();
+
+        Self {
+            data,
+            words: space.words().to_vec(),
+            norms,
+            dimension: space.dim(),
+            scale,
+        }
+    }
+
+    /// Returns the amount of vectors in the space.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns `true` if the space is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the dimension of the vectors.
+    #[inline]
+    pub fn dim(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the value of a single quantization step.
+    #[inline]
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Returns the term of the vector with the ID `idx`.
+    #[inline]
+    pub fn term(&self, idx: usize) -> Option<&str> {
+        self.words.get(idx).map(|i| i.as_str())
+    }
+
+    /// Returns the quantized components of the vector with the ID `idx`.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<&[i8]> {
+        if idx >= self.len() {
+            return None;
+        }
+        let start = idx * self.dimension;
+        Some(&self.data[start..start + self.dimension])
+    }
+
+    /// Quantizes `query` with the scale of the space. Components outside of the range of the
+    /// space get clamped, which lowers the accuracy for queries much longer than the vectors in
+    /// the space. Components much smaller than [`QuantizedVecSpace::scale`] become `0` and are
+    /// ignored in the similarity, so queries should have a similar magnitude as the vectors in
+    /// the space.
+    pub fn quantize_query<'v, 't, R>(&self, query: &R) -> QuantizedQuery
+    where
+        R: AsVectorRef<'v, 't>,
+    {
+        let query = query.as_vec_ref();
+        let data: Vec<i8> = query
+            .data()
+            .iter()
+            .map(|i| quantize(*i, self.scale))
+            .collect();
+        let norm = norm(&data);
+        QuantizedQuery { data, norm }
+    }
+
+    /// Find the `k` vectors with the highest cosine similarity to `query` using integer dot
+    /// products. Returns the approximated similarities together with the terms. Vectors with a
+    /// non-finite score are never returned.
+    pub fn top_k_quantized(&self, k: usize, query: &QuantizedQuery) -> Vec<(f32, &str)> {
+        if k == 0 || query.data.len() != self.dimension {
+            return vec![];
+        }
+
+        let mut cont = priority_container::PrioContainerMax::new(k);
+        for (id, norm) in self.norms.iter().enumerate() {
+            let div = query.norm * norm;
+            let score = if div == 0.0 {
+                0.0
+            } else {
+                dot(&query.data, self.get(id).unwrap()) as f32 / div
+            };
+            if score.is_finite() {
+                cont.insert(OrderVal::new(id, FloatOrd(score)));
+            }
+        }
+
+        let mut res: Vec<_> = cont
+            .into_iter()
+            .map(|i| (i.0.ord().0, self.words[i.0.into_inner()].as_str()))
+            .collect();
+        res.reverse();
+        res
+    }
+}
+
+impl From<&VecSpace> for QuantizedVecSpace {
+    #[inline]
+    fn from(space: &VecSpace) -> Self {
+        Self::new(space)
+    }
+}
+
+impl QuantizedQuery {
+    /// Returns the quantized components of the query.
+    #[inline]
+    pub fn data(&self) -> &[i8] {
+        &self.data
+    }
+}
+
+#[inline]
+fn quantize(val: f32, scale: f32) -> i8 {
+    (val / scale)
+        .round()
+        .clamp(-(i8::MAX as f32), i8::MAX as f32) as i8
+}
+
+#[inline]
+fn dot(a: &[i8], b: &[i8]) -> i32 {
+    a.iter().zip(b).map(|(a, b)| *a as i32 * *b as i32).sum()
+}
+
+#[inline]
+fn norm(v: &[i8]) -> f32 {
+    (dot(v, v) as f32).sqrt()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vector::Vector;
+
+    #[test]
+    fn test_top_k_quantized() {
+        let mut space = VecSpace::new(3);
+        space.extend([
+            Vector::new(&[1.0, 0.07, 23.1], "a"),
+            Vector::new(&[0.13, 3.19, 3.12], "b"),
+            Vector::new(&[3.193, 3.1, 32.1], "c"),
+            Vector::new(&[-2.0, 1.5, -0.3], "d"),
+        ]);
+        let quantized = QuantizedVecSpace::new(&space);
+        assert_eq!(quantized.len(), 4);
+        assert_eq!(quantized.dim(), 3);
+
+        let query = Vector::new(&[0.1, 3.0, 3.0], "q");
+        let exp = space.top_k(4, |v| v.cosine(&query));
+        let res = quantized.top_k_quantized(4, &quantized.quantize_query(&query));
+
+        assert_eq!(res.len(), exp.len());
+        for (got, exp) in res.iter().zip(&exp) {
+            assert_eq!(got.1, exp.1.term());
+            assert!((got.0 - exp.0).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_quantize_non_finite() {
+        let mut space = VecSpace::new(2);
+        space.extend([
+            Vector::new(&[1.0, 2.0], "a"),
+            Vector::new(&[f32::NAN, f32::INFINITY], "b"),
+        ]);
+        let quantized = QuantizedVecSpace::new(&space);
+        assert_eq!(quantized.scale(), 2.0 / 127.0);
+        assert_eq!(quantized.get(1), Some(&[0, 127][..]));
+
+        let query = quantized.quantize_query(&Vector::new(&[1.0, 2.0], "q"));
+        let res = quantized.top_k_quantized(2, &query);
+        assert_eq!(res.len(), 2);
+        assert!(res.iter().all(|i| i.0.is_finite()));
+        assert_eq!(res[0].1, "a");
+    }
+
+    #[test]
+    fn test_quantize_zero_dim() {
+        let mut space = VecSpace::new(0);
+        space.extend([Vector::new(&[], "a"), Vector::new(&[], "b")]);
+        let quantized = QuantizedVecSpace::new(&space);
+        assert_eq!(quantized.len(), 2);
+        assert_eq!(quantized.get(1), Some(&[][..]));
+
+        let query = quantized.quantize_query(&Vector::new(&[], "q"));
+        let res = quantized.top_k_quantized(2, &query);
+        assert_eq!(res.len(), 2);
+        assert!(res.iter().all(|i| i.0 == 0.0));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{as_vector::AsVectorRef, space::VecSpace, vector::Vector};
+
+/// A single result of a search in a [`VecSpace`], see [`VecSpace::search`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchHit<'a> {
+    score: f32,
+    vector: Vector<'a, 'a>,
+}
+
+impl<'a> SearchHit<'a> {
+    #[inline]
+    pub(crate) fn new(score: f32, vector: Vector<'a, 'a>) -> Self {
+        Self { score, vector }
+    }
+
+    /// Returns the score of the hit.
+    #[inline]
+    pub fn score(&self) -> f32 {
+        self.score
+    }
+
+    /// Returns the matched vector.
+    #[inline]
+    pub fn vector(&self) -> Vector<'a, 'a> {
+        self.vector
+    }
+
+    /// Returns the term of the matched vector.
+    #[inline]
+    pub fn term(&self) -> &str {
+        self.vector.term()
+    }
+}
+
+impl<'a> From<(f32, Vector<'a, 'a>)> for SearchHit<'a> {
+    #[inline]
+    fn from((score, vector): (f32, Vector<'a, 'a>)) -> Self {
+        Self::new(score, vector)
+    }
+}
+
+/// Read-only search handle for a [`VecSpace`] with precomputed lengths of all vectors. The view
+/// is `Send + Sync` so it can be shared between threads to search the space concurrently.
+///
+/// Created by [`VecSpace::search_view`].
+#[derive(Clone, Debug)]
+pub struct SearchView<'a> {
+    space: &'a VecSpace,
+    norms: Vec<f32>,
+}
+
+impl<'a> SearchView<'a> {
+    #[inline]
+    pub(crate) fn new(space: &'a VecSpace) -> Self {
+        let norms = space.iter().map(|i| i.length()).collect();
+        Self { space, norms }
+    }
+
+    /// Returns the underlying space.
+    #[inline]
+    pub fn space(&self) -> &'a VecSpace {
+        self.space
+    }
+
+    /// Find `k` most similar vectors using `sim` as similarity function. See [`VecSpace::top_k`].
+    #[inline]
+    pub fn top_k<S>(&self, k: usize, sim: S) -> Vec<(f32, Vector<'a, 'a>)>
+    where
+        S: Fn(&Vector) -> f32,
+    {
+        self.space.top_k(k, sim)
+    }
+
+    /// Find the `k` vectors with the highest cosine similarity to `query` using the precomputed
+    /// lengths of the vectors.
+    pub fn most_similar<'v, 't, R>(&self, query: &R, k: usize) -> Vec<(f32, Vector<'a, 'a>)>
+    where
+        R: AsVectorRef<'v, 't>,
+    {
+        let query = query.as_vec_ref();
+        let query_len = query.length();
+
+        let scored = self.space.iter().zip(&self.norms).map(|(v, norm)| {
+            let div = query_len * norm;
+            if div == 0.0 {
+                return (0.0, v);
+            }
+            (query.dot(&v) / div, v)
+        });
+        VecSpace::top_k_scored(k, scored)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_search_view_threads() {
+        let mut space = VecSpace::new(3);
+        space.extend([
+            Vector::new(&[1.0, 0.07, 23.1], "a"),
+            Vector::new(&[0.13, 3.19, 3.12], "b"),
+            Vector::new(&[3.193, 3.1, 32.1], "c"),
+        ]);
+
+        let view = space.search_view();
+        let query = Vector::new(&[0.1, 3.0, 3.0], "q");
+        let expected = space.top_k(2, |v| v.cosine(&query));
+
+        std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| s.spawn(|| view.most_similar(&query, 2)))
+                .collect();
+
+            for handle in handles {
+                let res = handle.join().unwrap();
+                assert_eq!(res.len(), expected.len());
+                for (got, exp) in res.iter().zip(&expected) {
+                    assert_eq!(got.1, exp.1);
+                    assert!((got.0 - exp.0).abs() < 1e-6);
+                }
+            }
+        });
+    }
+}
@@ -1,11 +1,22 @@
-use crate::{as_vector::AsVectorRef, error::Error, iter::VecSpaceIter, vector::Vector};
+use crate::{
+    as_vector::AsVectorRef,
+    diff::SpaceDiff,
+    error::Error,
+    iter::VecSpaceIter,
+    metric::Metric,
+    search::{SearchHit, SearchView},
+    stats::{DimStat, NormStats},
+    term_map::TermMap,
+    vector::{OwnedVector, Vector},
+};
 use ahash::AHashMap;
+use nalgebra::DMatrixView;
 use order_struct::{float_ord::FloatOrd, OrderVal};
-use std::slice::Iter;
+use std::{collections::BTreeMap, io::Write, slice::Iter};
 
 /// A highly memory optimized vector space that can handle a lot of high dimensional word vecs with as few
 /// memory overhead as possible.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VecSpace {
     /// A big vector for vector data. Since all vectors have the same dimension we can simply
@@ -19,7 +30,33 @@ pub struct VecSpace {
     dimension: usize,
 
     /// Index for terms to their ID.
-    pub term_map: Option<AHashMap<String, u32>>,
+    pub term_map: Option<TermMap>,
+
+    /// IDs of all vectors sorted by their terms. A memory efficient alternative to `term_map`
+    /// which finds terms using binary search.
+    #[cfg_attr(feature = "serde", serde(default))]
+    sorted_index: Option<Vec<u32>>,
+
+    /// Amount of vectors declared in the header of the parsed file(s).
+    #[cfg_attr(feature = "serde", serde(default))]
+    declared_len: Option<usize>,
+
+    /// Minimum and maximum of each dimension, updated on every insert if enabled.
+    #[cfg_attr(feature = "serde", serde(default))]
+    dim_bounds: Option<Vec<(f32, f32)>>,
+}
+
+/// Decides which vector is kept for duplicate terms in [`VecSpace::dedup_terms`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DedupPolicy {
+    /// Keep the first vector of the term.
+    KeepFirst,
+
+    /// Keep the last vector of the term.
+    KeepLast,
+
+    /// Replace the first vector of the term with the average of all its vectors.
+    Average,
 }
 
 impl VecSpace {
@@ -31,14 +68,205 @@ impl VecSpace {
             words: vec![],
             dimension,
             term_map: None,
+            sorted_index: None,
+            declared_len: None,
+            dim_bounds: None,
+        }
+    }
+
+    /// Creates a space from its raw parts as returned by [`VecSpace::into_parts`]. `vec_data` has
+    /// to contain `dimension` components for each term in `words`, otherwise
+    /// [`Error::LengthMismatch`] is returned. `term_map` is used as is and has to map the terms to
+    /// their position in `words`.
+    pub fn from_raw_parts(
+        dimension: usize,
+        vec_data: Vec<f32>,
+        words: Vec<String>,
+        term_map: Option<AHashMap<String, u32>>,
+    ) -> Result<Self, Error> {
+        if vec_data.len() != words.len() * dimension {
+            return Err(Error::LengthMismatch(
+                vec_data.len(),
+                words.len() * dimension,
+            ));
+        }
+
+        Ok(Self {
+            vec_data,
+            words,
+            dimension,
+            term_map: term_map.map(TermMap::Hash),
+            sorted_index: None,
+            declared_len: None,
+            dim_bounds: None,
+        })
+    }
+
+    /// Consumes the space and returns its dimension, vector data, terms and term map without
+    /// copying them. A sorted term map gets converted into a hash map and a sorted index gets
+    /// dropped. See [`VecSpace::from_raw_parts`].
+    #[allow(clippy::type_complexity)]
+    pub fn into_parts(self) -> (usize, Vec<f32>, Vec<String>, Option<AHashMap<String, u32>>) {
+        let term_map = self.term_map.map(TermMap::into_hash_map);
+        (self.dimension, self.vec_data, self.words, term_map)
+    }
+
+    /// Creates a space with `count` random vectors with the terms `w0` to `w<count - 1>`. The
+    /// components are drawn from a standard normal distribution. Using the same `seed` always
+    /// results in the same space. Useful for benchmarks.
+    #[cfg(feature = "rand")]
+    pub fn random(dim: usize, count: usize, seed: u64) -> Self {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        // Box-Muller transform. `1.0 - gen()` is in (0, 1] so the logarithm is finite.
+        let mut gaussian = || {
+            let u1: f32 = 1.0 - rng.gen::<f32>();
+            let u2: f32 = rng.gen();
+            (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+        };
+
+        let mut space = Self::new(dim);
+        space.vec_data = (0..dim * count).map(|_| gaussian()).collect();
+        space.words = (0..count).map(|i| format!("w{i}")).collect();
+        space
+    }
+
+    /// Creates a new space containing all terms of the given spaces. The vector of each term is the
+    /// average of its vectors in all spaces that contain it. All spaces must have the same
+    /// dimension, otherwise [`Error::DimMismatch`] is returned.
+    pub fn average_spaces(spaces: &[VecSpace]) -> Result<Self, Error> {
+        let Some(first) = spaces.first() else {
+            return Ok(Self::new(0));
+        };
+        let dim = first.dimension;
+
+        let mut ids: ahash::AHashMap<&str, usize> = ahash::AHashMap::new();
+        let mut words = vec![];
+        let mut sums: Vec<f32> = vec![];
+        let mut counts: Vec<u32> = vec![];
+
+        for space in spaces {
+            if space.dimension != dim {
+                return Err(Error::DimMismatch(space.dimension, dim));
+            }
+
+            for (pos, term) in space.words.iter().enumerate() {
+                let id = *ids.entry(term.as_str()).or_insert_with(|| {
+                    words.push(term.clone());
+                    sums.resize(sums.len() + dim, 0.0);
+                    counts.push(0);
+                    words.len() - 1
+                });
+
+                let data = &space.vec_data[pos * dim..(pos + 1) * dim];
+                for (s, v) in sums[id * dim..(id + 1) * dim].iter_mut().zip(data) {
+                    *s += v;
+                }
+                counts[id] += 1;
+            }
+        }
+
+        if dim > 0 {
+            for (vec, count) in sums.chunks_exact_mut(dim).zip(counts) {
+                vec.iter_mut().for_each(|i| *i /= count as f32);
+            }
         }
+
+        let mut space = Self::new(dim);
+        space.vec_data = sums;
+        space.words = words;
+        Ok(space)
     }
 
     /// Enables mapping for terms to vectors. This requires more memory but makes searching for
     /// terms faster. Existing terms will be indexed when calling this function.
+    ///
+    /// Indexed spaces can hold at most `u32::MAX + 1` vectors. Inserting more vectors fails with
+    /// [`Error::IndexOverflow`].
+    ///
+    /// # Panics
+    /// Panics if the space already contains more than `u32::MAX + 1` vectors.
+    #[inline]
+    pub fn with_termmap(self) -> Self {
+        self.with_termmap_backend(TermMap::default())
+    }
+
+    /// Like [`VecSpace::with_termmap`] but keeps the indexed terms sorted which allows iterating
+    /// over the terms in lexicographical order using [`VecSpace::terms_sorted`]. Lookups are
+    /// slower than with the default term map.
+    ///
+    /// # Panics
+    /// Panics if the space already contains more than `u32::MAX + 1` vectors.
+    #[inline]
+    pub fn with_sorted_termmap(self) -> Self {
+        self.with_termmap_backend(TermMap::BTree(BTreeMap::new()))
+    }
+
+    /// Enables a sorted index for terms as memory efficient alternative to the term map. Terms
+    /// are searched using binary search which is a bit slower than using the term map. Inserting
+    /// vectors becomes slower too as the index has to be kept sorted. Existing terms will be
+    /// indexed when calling this function and an existing term map gets removed.
+    ///
+    /// # Panics
+    /// Panics if the space already contains more than `u32::MAX + 1` vectors.
+    pub fn with_sorted_index(mut self) -> Self {
+        self.term_map = None;
+        self.sorted_index = Some(vec![]);
+        self.index_terms();
+        self
+    }
+
+    /// Enables tracking of the minimum and maximum of each dimension while inserting vectors. The
+    /// bounds of existing vectors get calculated when calling this function. Methods that modify
+    /// or remove vectors recalculate the bounds, so they always match the vectors in the space.
+    pub fn track_stats(mut self) -> Self {
+        self.dim_bounds = Some(self.calc_dim_bounds());
+        self
+    }
+
+    /// Returns the minimum and maximum of each dimension if tracking was enabled using
+    /// [`VecSpace::track_stats`] and the space isn't empty.
     #[inline]
-    pub fn with_termmap(mut self) -> Self {
-        self.term_map = Some(AHashMap::new());
+    pub fn dim_bounds(&self) -> Option<&[(f32, f32)]> {
+        if self.is_empty() {
+            return None;
+        }
+        self.dim_bounds.as_deref()
+    }
+
+    /// Rescales each dimension into `[0, 1]` using min-max normalization. The tracked bounds of
+    /// [`VecSpace::track_stats`] are used if enabled, otherwise they get calculated. Dimensions
+    /// in which all vectors have the same value are set to `0`.
+    pub fn minmax_normalize(&mut self) {
+        if self.is_empty() || self.dimension == 0 {
+            return;
+        }
+
+        let tracked = self.dim_bounds.is_some();
+        let bounds = self
+            .dim_bounds
+            .take()
+            .unwrap_or_else(|| self.calc_dim_bounds());
+
+        for vec in self.vec_data.chunks_exact_mut(self.dimension) {
+            for (v, (min, max)) in vec.iter_mut().zip(&bounds) {
+                let range = max - min;
+                *v = if range > 0.0 { (*v - min) / range } else { 0.0 };
+            }
+        }
+
+        if tracked {
+            self.dim_bounds = Some(bounds);
+            self.refresh_dim_bounds();
+        }
+    }
+
+    /// Enables mapping for terms to vectors using the given (empty) term map.
+    fn with_termmap_backend(mut self, term_map: TermMap) -> Self {
+        self.term_map = Some(term_map);
+        self.sorted_index = None;
 
         if !self.is_empty() {
             self.index_terms();
@@ -53,6 +281,20 @@ impl VecSpace {
         self.words.len()
     }
 
+    /// Amount of vectors declared in the header of the file(s) the space was parsed from. This can
+    /// differ from [`VecSpace::len`] for truncated files. Returns `None` if the space wasn't
+    /// parsed from a file with a header.
+    #[inline]
+    pub fn declared_len(&self) -> Option<usize> {
+        self.declared_len
+    }
+
+    /// Adds `len` to the declared amount of vectors.
+    #[inline]
+    pub(crate) fn add_declared_len(&mut self, len: usize) {
+        self.declared_len = Some(self.declared_len.unwrap_or(0) + len);
+    }
+
     /// Returns `true` if there is no vec in the vec space.
     #[inline]
     pub fn is_empty(&self) -> bool {
@@ -72,6 +314,26 @@ impl VecSpace {
         if let Some(term_map) = self.term_map.as_mut() {
             term_map.shrink_to_fit();
         }
+        if let Some(sorted_index) = self.sorted_index.as_mut() {
+            sorted_index.shrink_to_fit();
+        }
+    }
+
+    /// Like [`VecSpace::shrink_to_fit`] but moves the vector data into a new allocation of the
+    /// exact size and rebuilds the term index from scratch. This releases the excess memory left
+    /// by removing many vectors, eg. with [`VecSpace::remove_zero_vectors`].
+    pub fn compact(&mut self) {
+        self.vec_data = self.vec_data.to_vec();
+        self.words.shrink_to_fit();
+
+        if let Some(term_map) = self.term_map.as_mut() {
+            term_map.clear();
+            term_map.shrink_to_fit();
+        }
+        if let Some(sorted_index) = self.sorted_index.as_mut() {
+            *sorted_index = Vec::with_capacity(self.words.len());
+        }
+        self.index_terms();
     }
 
     /// Returns the total capacity of the vector spaces allocation.
@@ -79,6 +341,11 @@ impl VecSpace {
         self.words.capacity()
             + self.vec_data.capacity()
             + self.term_map.as_ref().map(|i| i.capacity()).unwrap_or(0)
+            + self
+                .sorted_index
+                .as_ref()
+                .map(|i| i.capacity())
+                .unwrap_or(0)
     }
 
     /// Reservers capacity for at least `additional` more vectors.
@@ -87,6 +354,14 @@ impl VecSpace {
         self.vec_data.reserve(additional * self.dimension);
     }
 
+    /// Returns the amount of bytes the space takes when exported in binary word2vec format using
+    /// [`Exporter`](crate::export::Exporter), including the header.
+    pub fn binary_size_estimate(&self) -> usize {
+        let header = format!("{} {}\n", self.len(), self.dimension).len();
+        let terms: usize = self.words.iter().map(|i| i.len() + 1).sum();
+        header + terms + self.vec_data.len() * std::mem::size_of::<f32>()
+    }
+
     /// Returns an iterator over all vectors in the space.
     #[inline]
     pub fn iter(&self) -> VecSpaceIter {
@@ -98,6 +373,66 @@ impl VecSpace {
         self.words.iter()
     }
 
+    /// Returns all terms as slice. The position of a term equals the ID of its vector.
+    #[inline]
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Writes all terms, each followed by a newline, into `w`. Returns the amount of bytes written.
+    pub fn write_terms<W: Write>(&self, mut w: W) -> std::io::Result<usize> {
+        let mut n = 0;
+        for term in &self.words {
+            w.write_all(term.as_bytes())?;
+            w.write_all(b"\n")?;
+            n += term.len() + 1;
+        }
+        Ok(n)
+    }
+
+    /// Returns an iterator over all terms with the ID of their vector.
+    #[inline]
+    pub fn enumerate_terms(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.words.iter().map(|i| i.as_str()).enumerate()
+    }
+
+    /// Returns an iterator over chunks of `chunk_size` vectors, yielding the terms and the
+    /// contiguous vector data of each chunk. The last chunk may be smaller.
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is zero.
+    pub fn chunks(&self, chunk_size: usize) -> impl Iterator<Item = (&[String], &[f32])> {
+        self.words
+            .chunks(chunk_size)
+            .enumerate()
+            .map(move |(i, words)| {
+                let start = i * chunk_size * self.dimension;
+                let end = start + words.len() * self.dimension;
+                (words, &self.vec_data[start..end])
+            })
+    }
+
+    /// Returns an iterator over all unique terms in lexicographical order. This doesn't require
+    /// any sorting if the space was created using [`VecSpace::with_sorted_termmap`].
+    pub fn terms_sorted(&self) -> impl Iterator<Item = &str> {
+        let indexed = match &self.term_map {
+            Some(TermMap::BTree(map)) => Some(map.keys().map(|i| i.as_str())),
+            _ => None,
+        };
+
+        let sorted = indexed.is_none().then(|| {
+            let mut terms: Vec<_> = self.words.iter().map(|i| i.as_str()).collect();
+            terms.sort_unstable();
+            terms.dedup();
+            terms
+        });
+
+        indexed
+            .into_iter()
+            .flatten()
+            .chain(sorted.into_iter().flatten())
+    }
+
     /// Inserts a word vector into the vecspace. Returns an error if the dimensions don't match.
     pub fn insert<'v, 't, R: AsVectorRef<'v, 't>>(&mut self, vec: R) -> Result<(), Error> {
         let vec = vec.as_vec_ref();
@@ -105,15 +440,121 @@ impl VecSpace {
             return Err(Error::DimMismatch(vec.dim(), self.dim()));
         }
 
-        if let Some(term_map) = self.term_map.as_mut() {
-            term_map.insert(vec.term().to_string(), self.words.len() as u32);
+        if self.is_indexed() {
+            index_id(self.words.len())?;
         }
 
         self.vec_data.extend_from_slice(vec.data());
         self.words.push(vec.term().to_string());
+        self.extend_term_map(self.words.len() - 1);
+        self.update_dim_bounds(vec.data());
+        Ok(())
+    }
+
+    /// Copies all vectors into a 2D array with the shape `(len, dim)`.
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> ndarray::Array2<f32> {
+        ndarray::Array2::from_shape_vec((self.len(), self.dimension), self.vec_data.clone())
+            .expect("Vector data matches the spaces shape")
+    }
+
+    /// Creates a new space from a 2D array in which each row is a vector with the term at the
+    /// same position in `terms`. Returns [`Error::LengthMismatch`] if the amount of terms doesn't
+    /// match the amount of rows.
+    #[cfg(feature = "ndarray")]
+    pub fn from_ndarray(arr: ndarray::ArrayView2<f32>, terms: Vec<String>) -> Result<Self, Error> {
+        if terms.len() != arr.nrows() {
+            return Err(Error::LengthMismatch(terms.len(), arr.nrows()));
+        }
+
+        let mut space = Self::new(arr.ncols());
+        space.vec_data = arr.iter().copied().collect();
+        space.words = terms;
+        Ok(space)
+    }
+
+    /// Inserts all vectors of `iter` into the vecspace and returns the amount of inserted vectors.
+    /// Stops at the first vector with a different dimension and returns an error. Vectors before
+    /// that vector stay inserted.
+    pub fn try_extend<'v, 't, I, R>(&mut self, iter: I) -> Result<usize, Error>
+    where
+        I: IntoIterator<Item = R>,
+        R: AsVectorRef<'v, 't>,
+    {
+        let mut n = 0;
+        for vec in iter {
+            self.insert(vec)?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    /// Appends all vectors of `other` to the space. Duplicate terms are not handled in any way.
+    /// Returns an error if the dimensions of the spaces differ.
+    pub fn extend_from(&mut self, other: &VecSpace) -> Result<(), Error> {
+        if other.dimension != self.dimension {
+            return Err(Error::DimMismatch(other.dimension, self.dimension));
+        }
+
+        let start = self.words.len();
+        if self.is_indexed() && !other.is_empty() {
+            index_id(start + other.len() - 1)?;
+        }
+
+        self.vec_data.extend_from_slice(&other.vec_data);
+        self.words.extend_from_slice(&other.words);
+        for vec in other.iter() {
+            self.update_dim_bounds(vec.data());
+        }
+        self.extend_term_map(start);
+        Ok(())
+    }
+
+    /// Extends the space with owned vectors which get collected and validated in parallel. The
+    /// vectors are appended in the order of `iter`, resulting in the same space as a serial
+    /// [`VecSpace::try_extend`]. If any vector has a different dimension, an error is returned and
+    /// the space stays unchanged.
+    #[cfg(feature = "rayon")]
+    pub fn par_extend<I>(&mut self, iter: I) -> Result<(), Error>
+    where
+        I: rayon::iter::IntoParallelIterator<Item = OwnedVector>,
+    {
+        use rayon::prelude::*;
+
+        let dim = self.dimension;
+        let vecs: Vec<OwnedVector> = iter
+            .into_par_iter()
+            .map(|v| {
+                if v.dim() != dim {
+                    return Err(Error::DimMismatch(v.dim(), dim));
+                }
+                Ok(v)
+            })
+            .collect::<Result<_, _>>()?;
+
+        let start = self.words.len();
+        if self.is_indexed() && !vecs.is_empty() {
+            index_id(start + vecs.len() - 1)?;
+        }
+
+        self.reserve(vecs.len());
+        for vec in &vecs {
+            self.vec_data.extend_from_slice(vec.data());
+            self.words.push(vec.term().to_string());
+            self.update_dim_bounds(vec.data());
+        }
+        self.extend_term_map(start);
         Ok(())
     }
 
+    /// Inserts a vector without a term. The term `#<id>` gets generated for it, where `<id>` is the
+    /// ID of the new vector, which gets returned.
+    pub fn push_anonymous(&mut self, data: &[f32]) -> Result<usize, Error> {
+        let id = self.len();
+        self.insert(Vector::new(data, &format!("#{id}")))?;
+        Ok(id)
+    }
+
     /// Gets a vector with a given ID from the space.
     pub fn get(&self, pos: usize) -> Option<Vector> {
         let vec_idx = pos * self.dimension;
@@ -122,127 +563,2274 @@ impl VecSpace {
         Some(Vector::new(vec_data, word))
     }
 
-    /// Find `k` most similar vectors using `sim` as similarity funciton without allocating more
-    /// than `k` items.
-    pub fn top_k<S>(&self, k: usize, sim: S) -> Vec<(f32, Vector)>
+    /// Returns the score of the `k`-th most similar vector using `sim` as similarity function.
+    /// Returns `None` if `k` is zero or the space has less than `k` vectors with a score other than
+    /// NaN. NaN scores are skipped as in [`VecSpace::top_k`].
+    pub fn kth_nearest_score<S>(&self, k: usize, sim: S) -> Option<f32>
     where
         S: Fn(&Vector) -> f32,
     {
+        if k == 0 || k > self.len() {
+            return None;
+        }
+
         let mut cont = priority_container::PrioContainerMax::new(k);
+        let mut scored = 0;
+        for v in self.iter() {
+            let score = sim(&v);
+            if !score.is_nan() {
+                cont.insert(FloatOrd(score));
+                scored += 1;
+            }
+        }
 
-        for v in (0..self.len()).map(|i| self.get(i).unwrap()) {
-            let s = sim(&v);
-            cont.insert(OrderVal::new(v, FloatOrd(s)));
+        if scored < k {
+            return None;
         }
 
-        let mut res: Vec<_> = cont
-            .into_iter()
-            .map(|i| (i.0.ord().0, i.0.into_inner()))
-            .collect();
-        res.reverse();
-        res
+        // Items are yielded in ascending order.
+        cont.into_iter().next().map(|i| i.0 .0)
     }
 
-    /// Searches for a given term in the space
+    /// Gets an owned copy of the vector with a given ID from the space.
     #[inline]
-    pub fn find_term<S: AsRef<str>>(&self, term: S) -> Option<Vector> {
-        self.get(self.find_term_idx(term.as_ref())?)
+    pub fn get_owned(&self, pos: usize) -> Option<OwnedVector> {
+        self.get(pos).map(|i| borrowme::ToOwned::to_owned(&i))
     }
 
-    /// Clears the vectors from the space.
-    pub fn clear(&mut self) {
-        self.vec_data.clear();
-        self.words.clear();
-        if let Some(term_map) = self.term_map.as_mut() {
-            term_map.clear();
-        }
+    /// Returns an iterator over all vectors together with their score calculated by `sim`. The
+    /// scores are calculated lazily, allowing custom selection of the vectors.
+    pub fn scored<'a, S>(&'a self, sim: S) -> impl Iterator<Item = (f32, Vector<'a, 'a>)>
+    where
+        S: Fn(&Vector) -> f32 + 'a,
+    {
+        self.iter().map(move |v| (sim(&v), v))
     }
 
-    /// Returns the vec ID of the given term
+    /// Creates a read-only search handle with precomputed vector lengths which can be shared
+    /// between threads.
     #[inline]
-    fn find_term_idx(&self, term: &str) -> Option<usize> {
-        self.term_map.as_ref()?.get(term).map(|i| *i as usize)
+    pub fn search_view(&self) -> SearchView<'_> {
+        SearchView::new(self)
     }
 
-    /// Indexes the existing vectors.
-    fn index_terms(&mut self) {
-        let mut map = self.term_map.take().unwrap_or_default();
-        map.clear();
+    /// Find `k` most similar vectors using `sim` as similarity function without allocating more
+    /// than `k` items. Vectors with a NaN score are never returned.
+    pub fn top_k<S>(&self, k: usize, sim: S) -> Vec<(f32, Vector)>
+    where
+        S: Fn(&Vector) -> f32,
+    {
+        Self::top_k_scored(k, self.iter().map(|v| (sim(&v), v)))
+    }
 
-        for (pos, term) in self.words.iter().cloned().enumerate() {
-            map.insert(term, pos as u32);
+    /// Find the `k` least similar vectors using `sim` as similarity function, eg. to explore
+    /// antonyms. The results are sorted by ascending score. Vectors with a NaN score are never
+    /// returned.
+    pub fn bottom_k<S>(&self, k: usize, sim: S) -> Vec<(f32, Vector<'_, '_>)>
+    where
+        S: Fn(&Vector) -> f32,
+    {
+        if k == 0 {
+            return vec![];
         }
 
-        self.term_map = Some(map);
+        // The highest negated scores are the lowest scores.
+        let mut res = Self::top_k_scored(k, self.iter().map(|v| (-sim(&v), v)));
+        res.iter_mut().for_each(|i| i.0 = -i.0);
+        res
     }
-}
 
-impl<'v, 't, V> Extend<V> for VecSpace
-where
-    V: AsVectorRef<'v, 't>,
-{
-    fn extend<T: IntoIterator<Item = V>>(&mut self, iter: T) {
-        for i in iter {
-            let i = i.as_vec_ref();
-            if self.insert(i).is_err() {
-                panic!(
-                    "Tried to insert a {} dimensional vec into a space with {} dimensions",
-                    i.dim(),
-                    self.dim(),
-                );
+    /// Like [`VecSpace::top_k`] but returns typed [`SearchHit`]s instead of tuples.
+    pub fn search<S>(&self, k: usize, sim: S) -> Vec<SearchHit<'_>>
+    where
+        S: Fn(&Vector) -> f32,
+    {
+        self.top_k(k, sim)
+            .into_iter()
+            .map(SearchHit::from)
+            .collect()
+    }
+
+    /// Like [`VecSpace::top_k`] but returns the results in an unspecified order. This is slightly
+    /// faster if the caller sorts the results anyway.
+    pub fn top_k_unsorted<S>(&self, k: usize, sim: S) -> Vec<(f32, Vector<'_, '_>)>
+    where
+        S: Fn(&Vector) -> f32,
+    {
+        let mut cont = priority_container::PrioContainerMax::new(k);
+
+        for v in self.iter() {
+            let score = sim(&v);
+            if !score.is_nan() {
+                cont.insert(OrderVal::new(v, FloatOrd(score)));
             }
         }
+
+        cont.into_iter()
+            .map(|i| (i.0.ord().0, i.0.into_inner()))
+            .collect()
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::VecSpace;
-    use crate::vector::Vector;
+    /// Like [`VecSpace::top_k`] but stops scanning the space as soon as a vector with a score of at
+    /// least `target` was found and `k` vectors were collected.
+    ///
+    /// This is approximate: vectors after the stopping point are never scored, so the result may
+    /// differ from an exhaustive [`VecSpace::top_k`].
+    pub fn top_k_with_early_exit<S>(
+        &self,
+        k: usize,
+        target: f32,
+        sim: S,
+    ) -> Vec<(f32, Vector<'_, '_>)>
+    where
+        S: Fn(&Vector) -> f32,
+    {
+        if k == 0 {
+            return vec![];
+        }
 
-    fn get_vectors() -> [Vector<'static, 'static>; 3] {
-        [
-            Vector::new(&[1.0, 0.07, 23.1], "a"),
-            Vector::new(&[0.13, 3.19, 3.12], "b"),
-            Vector::new(&[3.193, 3.1, 32.1], "c"),
-        ]
+        let mut found_target = false;
+        let mut collected = 0;
+        let scored = self.iter().map_while(|v| {
+            if found_target && collected >= k {
+                return None;
+            }
+            let score = sim(&v);
+            found_target |= score >= target;
+            collected += 1;
+            Some((score, v))
+        });
+        Self::top_k_scored(k, scored)
     }
 
-    fn get_space() -> VecSpace {
-        let mut space = VecSpace::new(3);
-        space.extend(get_vectors().iter());
-        space
+    /// Find `k` vectors with the highest cosine similarity to `query`. Works for spaces with
+    /// vectors of any length. The length of `query` only gets calculated once.
+    pub fn top_k_cosine<'v, 't, R>(&self, k: usize, query: &R) -> Vec<(f32, Vector<'_, '_>)>
+    where
+        R: AsVectorRef<'v, 't>,
+    {
+        let query = query.as_vec_ref();
+        let query_len = query.length();
+
+        let scored = self.iter().map(|v| {
+            let div = query_len * v.length();
+            if div == 0.0 {
+                return (0.0, v);
+            }
+            (query.dot(&v) / div, v)
+        });
+        Self::top_k_scored(k, scored)
     }
 
-    #[test]
-    fn test_space_get() {
-        let space = get_space();
-        let vectors = get_vectors();
+    /// Find `k` vectors with the highest cosine similarity to `query` which have a similarity of at
+    /// least `min_sim`. Returns less than `k` vectors if not enough vectors reach `min_sim`.
+    pub fn most_similar_above<'v, 't, R>(
+        &self,
+        query: &R,
+        k: usize,
+        min_sim: f32,
+    ) -> Vec<(f32, Vector<'_, '_>)>
+    where
+        R: AsVectorRef<'v, 't>,
+    {
+        let query = query.as_vec_ref();
+        let scored = self
+            .iter()
+            .map(|v| (query.cosine(&v), v))
+            .filter(|(s, _)| *s >= min_sim);
+        Self::top_k_scored(k, scored)
+    }
 
-        for (pos, exp_vec) in vectors.iter().enumerate() {
-            let vec = space.get(pos).unwrap();
-            assert_eq!(vec, *exp_vec);
+    /// Returns the `k` items with the highest score in descending order without allocating more
+    /// than `k` items.
+    pub(crate) fn top_k_scored<'a, I>(k: usize, scored: I) -> Vec<(f32, Vector<'a, 'a>)>
+    where
+        I: Iterator<Item = (f32, Vector<'a, 'a>)>,
+    {
+        let mut cont = priority_container::PrioContainerMax::new(k);
+
+        // NaN can't be ordered and would corrupt the order of the container.
+        for (s, v) in scored.filter(|i| !i.0.is_nan()) {
+            cont.insert(OrderVal::new(v, FloatOrd(s)));
+        }
+
+        let mut res: Vec<_> = cont
+            .into_iter()
+            .map(|i| (i.0.ord().0, i.0.into_inner()))
+            .collect();
+        res.reverse();
+        res
+    }
+
+    /// Calculates the average cosine similarity of the vector with the ID `idx` to all other
+    /// vectors. Returns `None` if there is no such vector or the space has no other vectors.
+    pub fn centrality(&self, idx: usize) -> Option<f32> {
+        let vec = self.get(idx)?;
+        if self.len() < 2 {
+            return None;
         }
+
+        let sum: f32 = self
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| *id != idx)
+            .map(|(_, other)| vec.cosine(&other))
+            .sum();
+        Some(sum / (self.len() - 1) as f32)
     }
 
-    #[test]
-    fn test_space_find() {
-        // test indexing after inserting.
-        let space = get_space().with_termmap();
-        let vectors = get_vectors();
+    /// Calculates [`VecSpace::centrality`] for all vectors. Returns an empty `Vec` if the space
+    /// has less than two vectors.
+    ///
+    /// Each pair of vectors gets compared once, so this takes O(n²) time.
+    pub fn centralities(&self) -> Vec<f32> {
+        if self.len() < 2 {
+            return vec![];
+        }
 
-        for exp_vec in vectors {
-            let vec = space.find_term(exp_vec.term()).unwrap();
-            assert_eq!(vec, exp_vec);
+        let mut sums = vec![0.0; self.len()];
+        for (i, a) in self.iter().enumerate() {
+            for (j, b) in self.iter().enumerate().skip(i + 1) {
+                let sim = a.cosine(&b);
+                sums[i] += sim;
+                sums[j] += sim;
+            }
         }
 
-        // test indexing while inserting.
-        let mut space = VecSpace::new(3).with_termmap();
-        let vectors = get_vectors();
-        space.extend(vectors);
-        for exp_vec in vectors {
-            let vec = space.find_term(exp_vec.term()).unwrap();
-            assert_eq!(vec, exp_vec);
+        let others = (self.len() - 1) as f32;
+        sums.iter_mut().for_each(|i| *i /= others);
+        sums
+    }
+
+    /// Represents each vector by its cosine similarities to the given `anchors`. The similarities
+    /// of each vector are in the same order as `anchors`.
+    pub fn anchor_embedding(&self, anchors: &[Vector]) -> Result<Vec<Vec<f32>>, Error> {
+        if let Some(anchor) = anchors.iter().find(|i| i.dim() != self.dimension) {
+            return Err(Error::DimMismatch(anchor.dim(), self.dimension));
+        }
+
+        let anchor_norms: Vec<f32> = anchors.iter().map(|i| i.length()).collect();
+
+        let res = self
+            .iter()
+            .map(|v| {
+                let norm = v.length();
+                anchors
+                    .iter()
+                    .zip(&anchor_norms)
+                    .map(|(a, a_norm)| {
+                        let div = norm * a_norm;
+                        if div == 0.0 {
+                            0.0
+                        } else {
+                            v.dot(a) / div
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(res)
+    }
+
+    /// Builds the k-nearest-neighbor graph of the space. For each vector, the IDs and cosine
+    /// similarities of the `k` most similar other vectors are returned in descending order.
+    ///
+    /// Each vector gets compared to all other vectors, so this takes O(n²) time.
+    pub fn knn_graph(&self, k: usize) -> Vec<Vec<(usize, f32)>> {
+        if k == 0 {
+            return vec![vec![]; self.len()];
+        }
+
+        let norms: Vec<f32> = self.iter().map(|i| i.length()).collect();
+
+        self.iter()
+            .enumerate()
+            .map(|(i, a)| {
+                let mut cont = priority_container::PrioContainerMax::new(k);
+
+                for (j, b) in self.iter().enumerate() {
+                    if i == j {
+                        continue;
+                    }
+
+                    let div = norms[i] * norms[j];
+                    let sim = if div == 0.0 { 0.0 } else { a.dot(&b) / div };
+                    if !sim.is_nan() {
+                        cont.insert(OrderVal::new(j, FloatOrd(sim)));
+                    }
+                }
+
+                let mut neighbors: Vec<_> = cont
+                    .into_iter()
+                    .map(|i| (i.0.into_inner(), i.0.ord().0))
+                    .collect();
+                neighbors.reverse();
+                neighbors
+            })
+            .collect()
+    }
+
+    /// Post-processes all vectors by first subtracting the centroid of the space from each vector
+    /// and then normalizing each vector to unit length.
+    pub fn postprocess(&mut self) {
+        if self.is_empty() || self.dimension == 0 {
+            return;
+        }
+
+        let centroid = self.centroid_data();
+
+        for vec in self.vec_data.chunks_exact_mut(self.dimension) {
+            for (v, c) in vec.iter_mut().zip(centroid.iter()) {
+                *v -= c;
+            }
+
+            let len = vec.iter().map(|i| i.powi(2)).sum::<f32>().sqrt();
+            if len != 0.0 {
+                vec.iter_mut().for_each(|v| *v /= len);
+            }
+        }
+
+        self.refresh_dim_bounds();
+    }
+
+    /// Solves the analogy "`a` is to `b` as `c` is to ?" by searching the vector most similar to
+    /// `b - a + c`. The input terms themselves are never returned. Returns `None` if any of the
+    /// terms isn't in the space. Terms are found as in [`VecSpace::term_index`] and vectors with a
+    /// NaN similarity are skipped.
+    pub fn best_analogy(&self, a: &str, b: &str, c: &str) -> Option<(f32, Vector<'_, '_>)> {
+        let ids = [
+            self.term_index(a)?,
+            self.term_index(b)?,
+            self.term_index(c)?,
+        ];
+        let [a, b, c] = ids.map(|i| self.get(i).unwrap());
+
+        let data: Vec<_> = b
+            .data()
+            .iter()
+            .zip(a.data())
+            .zip(c.data())
+            .map(|((b, a), c)| b - a + c)
+            .collect();
+        let target = Vector::new(&data, "");
+
+        (0..self.len())
+            .filter(|i| !ids.contains(i))
+            .map(|i| self.get(i).unwrap())
+            .map(|v| (target.cosine(&v), v))
+            .filter(|i| !i.0.is_nan())
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+    }
+
+    /// Rounds each component of all vectors to `decimals` decimal places. Useful to shrink the
+    /// size of exported txt files.
+    ///
+    /// An `f32` never needs more than 9 significant decimal digits, so for `decimals` of 9 or more
+    /// all components are left unchanged. Components which would overflow when being scaled, or
+    /// which have no fractional digits to round at the given precision, are left unchanged too.
+    pub fn round_components(&mut self, decimals: u32) {
+        if decimals >= 9 {
+            return;
+        }
+
+        // From 2^24 on all `f32` values are integers, so scaling back would only lose precision.
+        let max_scaled = 2f32.powi(f32::MANTISSA_DIGITS as i32);
+        let factor = 10f32.powi(decimals as i32);
+        for v in self.vec_data.iter_mut() {
+            let scaled = *v * factor;
+            if scaled.is_finite() && scaled.abs() < max_scaled {
+                *v = scaled.round() / factor;
+            }
+        }
+
+        self.refresh_dim_bounds();
+    }
+
+    /// Reorders the components of all vectors so that the `i`-th component becomes the
+    /// `perm[i]`-th component of the old vector. Returns [`Error::DimMismatch`] if `perm` doesn't
+    /// have the length of the dimension and [`Error::InvalidPermutation`] if it's not a
+    /// permutation of `0..dim`.
+    pub fn permute_dims(&mut self, perm: &[usize]) -> Result<(), Error> {
+        if perm.len() != self.dimension {
+            return Err(Error::DimMismatch(perm.len(), self.dimension));
+        }
+
+        let mut seen = vec![false; self.dimension];
+        for &p in perm {
+            if p >= self.dimension || std::mem::replace(&mut seen[p], true) {
+                return Err(Error::InvalidPermutation);
+            }
+        }
+
+        if self.dimension == 0 {
+            return Ok(());
+        }
+
+        let mut old = vec![0.0; self.dimension];
+        for vec in self.vec_data.chunks_exact_mut(self.dimension) {
+            old.copy_from_slice(vec);
+            for (v, p) in vec.iter_mut().zip(perm) {
+                *v = old[*p];
+            }
+        }
+
+        if let Some(bounds) = self.dim_bounds.as_mut() {
+            *bounds = perm.iter().map(|p| bounds[*p]).collect();
+        }
+        Ok(())
+    }
+
+    /// Clamps each component of all vectors into `[min, max]`.
+    ///
+    /// # Panics
+    /// Panics if `min > max` or either of them is NaN.
+    pub fn clamp_all(&mut self, min: f32, max: f32) {
+        for v in self.vec_data.iter_mut() {
+            *v = v.clamp(min, max);
+        }
+
+        self.refresh_dim_bounds();
+    }
+
+    /// Removes the component pointing into `direction` from all vectors, making all vectors
+    /// orthogonal to `direction`. Returns an error if the dimensions don't match.
+    pub fn remove_direction<'v, 't, R>(&mut self, direction: &R) -> Result<(), Error>
+    where
+        R: AsVectorRef<'v, 't>,
+    {
+        let direction = direction.as_vec_ref();
+        if direction.dim() != self.dimension {
+            return Err(Error::DimMismatch(direction.dim(), self.dim()));
+        }
+
+        let dir_dot = direction.dot(&direction);
+        if dir_dot == 0.0 || self.dimension == 0 {
+            return Ok(());
+        }
+
+        for vec in self.vec_data.chunks_exact_mut(self.dimension) {
+            let factor = direction.dot(&Vector::new(vec, "")) / dir_dot;
+            for (v, d) in vec.iter_mut().zip(direction.data()) {
+                *v -= factor * d;
+            }
+        }
+
+        self.refresh_dim_bounds();
+        Ok(())
+    }
+
+    /// Smooths all vectors by mixing each vector with the average of its `k` most similar vectors
+    /// (by cosine similarity), weighted by their similarity:
+    /// `self_weight * vec + (1 - self_weight) * neighbor_avg`. Neighbors with a negative similarity
+    /// are ignored. All neighbors are calculated on the original data, so updated vectors don't
+    /// affect each other. This compares all vectors with each other which is O(n²).
+    pub fn smooth(&mut self, k: usize, self_weight: f32) {
+        if k == 0 || self.len() < 2 {
+            return;
+        }
+
+        let mut snapshot = VecSpace::new(self.dimension);
+        snapshot.vec_data = self.vec_data.clone();
+        snapshot.words = self.words.clone();
+
+        let mut avg = vec![0.0; self.dimension];
+        for (i, vec) in self.vec_data.chunks_exact_mut(self.dimension).enumerate() {
+            let query = snapshot.get(i).unwrap();
+            let neighbors = snapshot
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, v)| (query.cosine(&v), v));
+
+            avg.iter_mut().for_each(|a| *a = 0.0);
+            let mut weight_sum = 0.0;
+            for (score, neighbor) in Self::top_k_scored(k, neighbors) {
+                let weight = score.max(0.0);
+                weight_sum += weight;
+                for (a, n) in avg.iter_mut().zip(neighbor.data()) {
+                    *a += weight * n;
+                }
+            }
+
+            if weight_sum == 0.0 {
+                continue;
+            }
+
+            for (v, a) in vec.iter_mut().zip(avg.iter()) {
+                *v = self_weight * *v + (1.0 - self_weight) * (a / weight_sum);
+            }
+        }
+
+        self.refresh_dim_bounds();
+    }
+
+    /// Multiplies each vector with `matrix`, which is a row-major `dim x dim` matrix, eg. an
+    /// orthogonal matrix aligning the space to another one. The dimension stays the same. Returns
+    /// [`Error::LengthMismatch`] if the size of the matrix doesn't match. The matrix is not
+    /// checked to actually be orthogonal.
+    #[inline]
+    pub fn apply_orthogonal(&mut self, matrix: &[f32]) -> Result<(), Error> {
+        self.apply_matrix(matrix, self.dimension)
+    }
+
+    /// Multiplies each vector with `matrix`, which is a row-major `new_dim x dim` matrix, changing
+    /// the dimension of the space to `new_dim`. Returns [`Error::LengthMismatch`] if the size of
+    /// the matrix doesn't match.
+    pub fn apply_matrix(&mut self, matrix: &[f32], new_dim: usize) -> Result<(), Error> {
+        let exp_len = new_dim * self.dimension;
+        if matrix.len() != exp_len {
+            return Err(Error::LengthMismatch(matrix.len(), exp_len));
+        }
+
+        // A row-major matrix is the transposed matrix in column-major order, and each vector is a
+        // column of the (column-major) vec data.
+        let matrix_t = DMatrixView::from_slice(matrix, self.dimension, new_dim);
+        let vecs = DMatrixView::from_slice(&self.vec_data, self.dimension, self.len());
+        let res = matrix_t.tr_mul(&vecs);
+
+        self.vec_data = res.as_slice().to_vec();
+        self.dimension = new_dim;
+
+        self.refresh_dim_bounds();
+        Ok(())
+    }
+
+    /// Calculates min, max, mean and standard deviation for each dimension in a single pass over
+    /// all vectors. Returns an empty list if the space is empty.
+    pub fn dim_stats(&self) -> Vec<DimStat> {
+        if self.is_empty() || self.dimension == 0 {
+            return vec![];
+        }
+
+        let init = DimStat {
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            mean: 0.0,
+            std: 0.0,
+        };
+        let mut stats = vec![init; self.dimension];
+
+        // Welford's algorithm. `std` holds the sum of squared differences until the end.
+        for (n, vec) in self.vec_data.chunks_exact(self.dimension).enumerate() {
+            let n = (n + 1) as f32;
+            for (stat, v) in stats.iter_mut().zip(vec) {
+                stat.min = stat.min.min(*v);
+                stat.max = stat.max.max(*v);
+
+                let delta = v - stat.mean;
+                stat.mean += delta / n;
+                stat.std += delta * (v - stat.mean);
+            }
+        }
+
+        let len = self.len() as f32;
+        for stat in stats.iter_mut() {
+            stat.std = (stat.std / len).sqrt();
+        }
+
+        stats
+    }
+
+    /// Calculates statistics of the lengths of all vectors. All values are zero if the space is
+    /// empty.
+    pub fn norm_stats(&self) -> NormStats {
+        if self.is_empty() {
+            return NormStats::default();
+        }
+
+        let mut norms: Vec<f32> = self.iter().map(|i| i.length()).collect();
+
+        let mut stats = NormStats {
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            ..NormStats::default()
+        };
+        for norm in &norms {
+            stats.min = stats.min.min(*norm);
+            stats.max = stats.max.max(*norm);
+            stats.mean += norm;
+        }
+        stats.mean /= norms.len() as f32;
+
+        norms.sort_unstable_by(|a, b| a.total_cmp(b));
+        let mid = norms.len() / 2;
+        stats.median = if norms.len() % 2 == 1 {
+            norms[mid]
+        } else {
+            (norms[mid - 1] + norms[mid]) / 2.0
+        };
+
+        stats
+    }
+
+    /// Returns the ID of the vector of the given term. Uses the term map or sorted index if
+    /// enabled, otherwise all terms get scanned. If a term occurs multiple times, the ID of the
+    /// last vector is returned.
+    pub fn term_index(&self, term: &str) -> Option<usize> {
+        if self.is_indexed() {
+            return self.find_term_idx(term);
+        }
+
+        self.words.iter().rposition(|i| i == term)
+    }
+
+    /// Searches for a given term in the space
+    #[inline]
+    pub fn find_term<S: AsRef<str>>(&self, term: S) -> Option<Vector> {
+        self.get(self.find_term_idx(term.as_ref())?)
+    }
+
+    /// Returns the cosine similarity between the vectors of the terms `a` and `b` or `None` if
+    /// either term doesn't exist. Terms are found as in [`VecSpace::term_index`].
+    #[inline]
+    pub fn similarity(&self, a: &str, b: &str) -> Option<f32> {
+        let (a, b) = self.term_pair(a, b)?;
+        Some(a.cosine(&b))
+    }
+
+    /// Returns the distance between the vectors of the terms `a` and `b` using `metric` or `None`
+    /// if either term doesn't exist.
+    #[inline]
+    pub fn distance(&self, a: &str, b: &str, metric: Metric) -> Option<f32> {
+        let (a, b) = self.term_pair(a, b)?;
+        Some(metric.distance(&a, &b))
+    }
+
+    /// Searches for a phrase of space separated words. Phrase models from word2vec store phrases
+    /// joined by underscores (eg. `New_York`) so this form is looked up first. Otherwise the
+    /// vectors of all words get summed up. Returns `None` if the phrase is empty or a word can't
+    /// be found. Terms are found as in [`VecSpace::term_index`].
+    pub fn find_phrase(&self, phrase: &str) -> Option<OwnedVector> {
+        let words: Vec<_> = phrase.split_whitespace().collect();
+        let (first, rest) = words.split_first()?;
+
+        if let Some(id) = self.term_index(&words.join("_")) {
+            return self.get_owned(id);
+        }
+
+        let mut vec = self.get_owned(self.term_index(first)?)?;
+        for word in rest {
+            vec = vec + self.get(self.term_index(word)?)?;
+        }
+        Some(vec)
+    }
+
+    /// Randomly shuffles the order of the vectors. Using the same `seed` always results in the
+    /// same order.
+    #[cfg(feature = "rand")]
+    pub fn shuffle(&mut self, seed: u64) {
+        use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+        let mut order: Vec<_> = (0..self.len()).collect();
+        order.shuffle(&mut StdRng::seed_from_u64(seed));
+        self.rebuild_from(&order);
+    }
+
+    /// Removes duplicate terms so each term only has a single vector. `policy` decides which
+    /// vector is kept.
+    pub fn dedup_terms(&mut self, policy: DedupPolicy) {
+        let dim = self.dimension;
+
+        // Maps each term to the ID of the vector that gets kept and the amount of its vectors.
+        let mut kept: ahash::AHashMap<&str, (usize, usize)> = ahash::AHashMap::new();
+        let mut sums: Vec<f32> = match policy {
+            DedupPolicy::Average => self.vec_data.clone(),
+            _ => vec![],
+        };
+
+        for (id, term) in self.words.iter().enumerate() {
+            let Some((kept_id, count)) = kept.get_mut(term.as_str()) else {
+                kept.insert(term, (id, 1));
+                continue;
+            };
+
+            *count += 1;
+            match policy {
+                DedupPolicy::KeepFirst => (),
+                DedupPolicy::KeepLast => *kept_id = id,
+                DedupPolicy::Average => {
+                    for d in 0..dim {
+                        sums[*kept_id * dim + d] += self.vec_data[id * dim + d];
+                    }
+                }
+            }
+        }
+
+        let mut ids: Vec<_> = kept.values().map(|i| i.0).collect();
+        ids.sort_unstable();
+
+        if policy == DedupPolicy::Average {
+            for (id, count) in kept.into_values() {
+                for d in 0..dim {
+                    sums[id * dim + d] /= count as f32;
+                }
+            }
+            self.vec_data = sums;
+        }
+
+        self.rebuild_from(&ids);
+    }
+
+    /// Swaps the positions of the vectors with the IDs `a` and `b`. Lookups for unique terms keep
+    /// resolving to the same vectors, duplicate terms keep resolving to the vector with the
+    /// highest ID.
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` are out of bounds.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        assert!(a < self.len() && b < self.len(), "Vector ID out of bounds");
+        if a == b {
+            return;
+        }
+
+        let dim = self.dimension;
+        self.words.swap(a, b);
+        for d in 0..dim {
+            self.vec_data.swap(a * dim + d, b * dim + d);
+        }
+
+        let swap_id = |id: u32| match id as usize {
+            i if i == a => b as u32,
+            i if i == b => a as u32,
+            _ => id,
+        };
+
+        // Equal terms keep the same set of IDs, so their map entry doesn't change.
+        if let Some(term_map) = self
+            .term_map
+            .as_mut()
+            .filter(|_| self.words[a] != self.words[b])
+        {
+            // The term at `new` was at `old` before.
+            for (old, new) in [(a, b), (b, a)] {
+                let term = &self.words[new];
+                if let Some(id) = term_map.get(term) {
+                    let id = id as usize;
+                    let max = if id != old {
+                        id.max(new)
+                    } else if new > old {
+                        new
+                    } else {
+                        // The highest remaining duplicate, unless none is left above `new`.
+                        (new + 1..old)
+                            .rev()
+                            .find(|i| self.words[*i] == *term)
+                            .unwrap_or(new)
+                    };
+                    term_map.insert(term.clone(), max as u32);
+                }
+            }
+        }
+
+        if let Some(sorted_index) = self.sorted_index.as_mut() {
+            for id in sorted_index.iter_mut() {
+                *id = swap_id(*id);
+            }
+
+            // Duplicate terms have to stay sorted by descending ID.
+            let words = &self.words;
+            for term in [&words[a], &words[b]] {
+                let start = sorted_index.partition_point(|i| words[*i as usize] < *term);
+                let end = sorted_index.partition_point(|i| words[*i as usize] <= *term);
+                sorted_index[start..end].sort_unstable_by(|x, y| y.cmp(x));
+            }
+        }
+    }
+
+    /// Replaces each term with the result of `f`. If multiple vectors end up with the same term,
+    /// only the first of them is kept.
+    pub fn map_terms<F: FnMut(&str) -> String>(&mut self, mut f: F) {
+        let mut seen = ahash::AHashSet::with_capacity(self.len());
+        let mut ids = Vec::with_capacity(self.len());
+
+        for (id, word) in self.words.iter_mut().enumerate() {
+            *word = f(word);
+            if seen.insert(word.as_str()) {
+                ids.push(id);
+            }
+        }
+
+        self.rebuild_from(&ids);
+    }
+
+    /// Removes vectors which are nearly identical to an earlier vector. A vector gets removed if
+    /// its cosine similarity to any kept vector is greater than `threshold`.
+    ///
+    /// Each vector is compared to all kept vectors, so this takes O(n²) time in the worst case.
+    pub fn dedup_similar(&mut self, threshold: f32) {
+        let mut kept: Vec<usize> = vec![];
+
+        for (id, vec) in self.iter().enumerate() {
+            let duplicate = kept
+                .iter()
+                .any(|k| self.get(*k).unwrap().cosine(&vec) > threshold);
+            if !duplicate {
+                kept.push(id);
+            }
+        }
+
+        self.rebuild_from(&kept);
+    }
+
+    /// Removes all vectors with a length below `epsilon`, eg. all-zero placeholders for unknown
+    /// words. Returns the amount of removed vectors.
+    pub fn remove_zero_vectors(&mut self, epsilon: f32) -> usize {
+        let ids: Vec<_> = self
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| v.length() >= epsilon)
+            .map(|(id, _)| id)
+            .collect();
+
+        let removed = self.len() - ids.len();
+        if removed > 0 {
+            self.rebuild_from(&ids);
+        }
+        removed
+    }
+
+    /// Retains only the vectors whose term also exists in `other`. Only terms are compared so the
+    /// dimensions of both spaces don't have to match.
+    pub fn intersect_terms(&mut self, other: &VecSpace) {
+        let other_terms: ahash::AHashSet<&str> = other.words.iter().map(|i| i.as_str()).collect();
+        let ids: Vec<_> = (0..self.len())
+            .filter(|i| other_terms.contains(self.words[*i].as_str()))
+            .collect();
+        self.rebuild_from(&ids);
+    }
+
+    /// Compares the space with `other`. Vectors of terms in both spaces count as changed if any
+    /// component differs by more than `epsilon`. Returns [`Error::DimMismatch`] if the dimensions
+    /// of both spaces differ.
+    ///
+    /// If a term occurs multiple times, only its last vector is compared.
+    pub fn diff<'a>(&'a self, other: &'a VecSpace, epsilon: f32) -> Result<SpaceDiff<'a>, Error> {
+        if other.dimension != self.dimension {
+            return Err(Error::DimMismatch(other.dimension, self.dimension));
+        }
+
+        let index = |space: &'a VecSpace| -> AHashMap<&'a str, usize> {
+            space
+                .enumerate_terms()
+                .map(|(id, term)| (term, id))
+                .collect()
+        };
+        let own_terms = index(self);
+        let other_terms = index(other);
+
+        let mut diff = SpaceDiff::default();
+
+        for (id, term) in self.enumerate_terms() {
+            if own_terms[term] != id {
+                continue;
+            }
+
+            let Some(other_id) = other_terms.get(term) else {
+                diff.removed.push(term);
+                continue;
+            };
+
+            let (own, other) = (self.get(id).unwrap(), other.get(*other_id).unwrap());
+            let changed = own
+                .data()
+                .iter()
+                .zip(other.data())
+                .any(|(a, b)| (a - b).abs() > epsilon);
+            if changed {
+                diff.changed.push(term);
+            }
+        }
+
+        for (id, term) in other.enumerate_terms() {
+            if other_terms[term] == id && !own_terms.contains_key(term) {
+                diff.added.push(term);
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// Picks `n` random pairs of vectors and returns their IDs together with their cosine
+    /// similarity. Using the same `seed` always results in the same pairs. Returns no pairs if the
+    /// space is empty.
+    #[cfg(feature = "rand")]
+    pub fn sample_pairs(&self, n: usize, seed: u64) -> Vec<(usize, usize, f32)> {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        if self.is_empty() {
+            return vec![];
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| {
+                let a = rng.gen_range(0..self.len());
+                let b = rng.gen_range(0..self.len());
+                let sim = self.get(a).unwrap().cosine(&self.get(b).unwrap());
+                (a, b, sim)
+            })
+            .collect()
+    }
+
+    /// Searches for a given term in the space and returns an owned copy of its vector.
+    #[inline]
+    pub fn find_term_owned<S: AsRef<str>>(&self, term: S) -> Option<OwnedVector> {
+        self.get_owned(self.find_term_idx(term.as_ref())?)
+    }
+
+    /// Clears the vectors from the space, together with the declared amount of vectors.
+    pub fn clear(&mut self) {
+        self.vec_data.clear();
+        self.words.clear();
+        self.declared_len = None;
+        if let Some(term_map) = self.term_map.as_mut() {
+            term_map.clear();
+        }
+        if let Some(sorted_index) = self.sorted_index.as_mut() {
+            sorted_index.clear();
+        }
+        self.refresh_dim_bounds();
+    }
+
+    /// Returns the vec ID of the given term
+    #[inline]
+    fn find_term_idx(&self, term: &str) -> Option<usize> {
+        if let Some(term_map) = self.term_map.as_ref() {
+            return term_map.get(term).map(|i| i as usize);
+        }
+
+        // Duplicate terms are sorted by descending ID so the last inserted one is found, as with
+        // the term map.
+        let sorted_index = self.sorted_index.as_ref()?;
+        let pos = sorted_index.partition_point(|i| self.words[*i as usize].as_str() < term);
+        let id = *sorted_index.get(pos)? as usize;
+        (self.words[id] == term).then_some(id)
+    }
+
+    /// Returns the vectors of the terms `a` and `b` if both exist.
+    #[inline]
+    fn term_pair(&self, a: &str, b: &str) -> Option<(Vector<'_, '_>, Vector<'_, '_>)> {
+        Some((
+            self.get(self.term_index(a)?)?,
+            self.get(self.term_index(b)?)?,
+        ))
+    }
+
+    /// Returns the component-wise mean of all vectors.
+    fn centroid_data(&self) -> Vec<f32> {
+        let mut centroid = vec![0.0; self.dimension];
+        if self.is_empty() {
+            return centroid;
+        }
+
+        for vec in self.vec_data.chunks_exact(self.dimension) {
+            for (c, v) in centroid.iter_mut().zip(vec) {
+                *c += v;
+            }
+        }
+
+        let len = self.len() as f32;
+        centroid.iter_mut().for_each(|c| *c /= len);
+        centroid
+    }
+
+    /// Rebuilds the space to only contain the vectors with the given IDs in the given order and
+    /// reindexes the terms. Each ID must only appear once.
+    fn rebuild_from(&mut self, ids: &[usize]) {
+        let dim = self.dimension;
+        let mut vec_data = Vec::with_capacity(ids.len() * dim);
+        let mut words = Vec::with_capacity(ids.len());
+
+        for &id in ids {
+            vec_data.extend_from_slice(&self.vec_data[id * dim..(id + 1) * dim]);
+            words.push(std::mem::take(&mut self.words[id]));
+        }
+
+        self.vec_data = vec_data;
+        self.words = words;
+
+        self.index_terms();
+        self.refresh_dim_bounds();
+    }
+
+    /// Calculates the minimum and maximum of each dimension.
+    fn calc_dim_bounds(&self) -> Vec<(f32, f32)> {
+        let mut bounds = vec![(f32::INFINITY, f32::NEG_INFINITY); self.dimension];
+        if self.dimension > 0 {
+            for vec in self.vec_data.chunks_exact(self.dimension) {
+                update_bounds(&mut bounds, vec);
+            }
+        }
+        bounds
+    }
+
+    /// Recalculates the tracked bounds after vectors were modified or removed if tracking is
+    /// enabled.
+    #[inline]
+    fn refresh_dim_bounds(&mut self) {
+        if self.dim_bounds.is_some() {
+            self.dim_bounds = Some(self.calc_dim_bounds());
+        }
+    }
+
+    /// Includes `data` in the tracked bounds if tracking is enabled.
+    #[inline]
+    fn update_dim_bounds(&mut self, data: &[f32]) {
+        if let Some(bounds) = self.dim_bounds.as_mut() {
+            update_bounds(bounds, data);
+        }
+    }
+
+    /// Returns `true` if either the term map or the sorted index is enabled.
+    #[inline]
+    fn is_indexed(&self) -> bool {
+        self.term_map.is_some() || self.sorted_index.is_some()
+    }
+
+    /// Indexes the existing vectors in the enabled term map or sorted index.
+    fn index_terms(&mut self) {
+        if let Some(term_map) = self.term_map.as_mut() {
+            term_map.clear();
+        }
+
+        // Sorting all IDs at once is faster than inserting them one by one.
+        let sorted_index = self.sorted_index.take();
+        self.extend_term_map(0);
+
+        if let Some(mut sorted_index) = sorted_index {
+            let words = &self.words;
+            sorted_index.clear();
+            sorted_index.extend((0..words.len()).map(|i| index_id(i).expect("Too many vectors")));
+            sorted_index.sort_by(|a, b| words[*a as usize].cmp(&words[*b as usize]).then(b.cmp(a)));
+            self.sorted_index = Some(sorted_index);
+        }
+    }
+
+    /// Indexes all vectors starting at `from_idx`. Vectors before `from_idx` have to be indexed
+    /// already. Does nothing if neither the term map nor the sorted index is enabled.
+    fn extend_term_map(&mut self, from_idx: usize) {
+        if let Some(term_map) = self.term_map.as_mut() {
+            for (pos, term) in self.words.iter().enumerate().skip(from_idx) {
+                term_map.insert(term.clone(), index_id(pos).expect("Too many vectors"));
+            }
+        }
+
+        if let Some(sorted_index) = self.sorted_index.as_mut() {
+            for (pos, term) in self.words.iter().enumerate().skip(from_idx) {
+                // New IDs are the largest ones so they have to be in front of equal terms.
+                let idx = sorted_index.partition_point(|i| self.words[*i as usize] < *term);
+                sorted_index.insert(idx, index_id(pos).expect("Too many vectors"));
+            }
+        }
+    }
+}
+
+/// Converts a vector ID into the `u32` stored in the term map and sorted index.
+#[inline]
+fn index_id(id: usize) -> Result<u32, Error> {
+    u32::try_from(id).map_err(|_| Error::IndexOverflow)
+}
+
+/// Extends the `(min, max)` bounds of each dimension with `data`.
+#[inline]
+fn update_bounds(bounds: &mut [(f32, f32)], data: &[f32]) {
+    for ((min, max), v) in bounds.iter_mut().zip(data) {
+        *min = min.min(*v);
+        *max = max.max(*v);
+    }
+}
+
+/// Compares the content of two spaces, ignoring the declared amount of vectors from parsing and
+/// tracked bounds.
+impl PartialEq for VecSpace {
+    fn eq(&self, other: &Self) -> bool {
+        self.vec_data == other.vec_data
+            && self.words == other.words
+            && self.dimension == other.dimension
+            && self.term_map == other.term_map
+            && self.sorted_index == other.sorted_index
+    }
+}
+
+/// Extends the space with vectors.
+///
+/// # Panics
+/// Panics if a vector can't be inserted, eg. because it has a different dimension than the space
+/// or an indexed space is full. Use [`VecSpace::try_extend`] to get an error instead.
+impl<'v, 't, V> Extend<V> for VecSpace
+where
+    V: AsVectorRef<'v, 't>,
+{
+    fn extend<T: IntoIterator<Item = V>>(&mut self, iter: T) {
+        for i in iter {
+            if let Err(err) = self.insert(i) {
+                panic!("Failed to extend space: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DedupPolicy, VecSpace};
+    use crate::metric::Metric;
+    use crate::stats::NormStats;
+    use crate::{error::Error, vector::Vector};
+
+    fn get_vectors() -> [Vector<'static, 'static>; 3] {
+        [
+            Vector::new(&[1.0, 0.07, 23.1], "a"),
+            Vector::new(&[0.13, 3.19, 3.12], "b"),
+            Vector::new(&[3.193, 3.1, 32.1], "c"),
+        ]
+    }
+
+    fn get_space() -> VecSpace {
+        let mut space = VecSpace::new(3);
+        space.extend(get_vectors().iter());
+        space
+    }
+
+    #[test]
+    fn test_space_get() {
+        let space = get_space();
+        let vectors = get_vectors();
+
+        for (pos, exp_vec) in vectors.iter().enumerate() {
+            let vec = space.get(pos).unwrap();
+            assert_eq!(vec, *exp_vec);
+        }
+    }
+
+    #[test]
+    fn test_into_parts() {
+        let space = get_space().with_termmap();
+        let (dim, vec_data, words, term_map) = space.clone().into_parts();
+        assert_eq!(dim, 3);
+        assert_eq!(words.len(), 3);
+        assert!(term_map.is_some());
+
+        let rebuilt = VecSpace::from_raw_parts(dim, vec_data, words, term_map).unwrap();
+        assert_eq!(rebuilt, space);
+        assert_eq!(rebuilt.find_term("b"), Some(get_vectors()[1]));
+
+        let (dim, vec_data, words, term_map) = get_space().with_sorted_termmap().into_parts();
+        assert_eq!(term_map.as_ref().map(|i| i.len()), Some(3));
+        let rebuilt = VecSpace::from_raw_parts(dim, vec_data, words, term_map).unwrap();
+        assert_eq!(rebuilt.find_term("c"), Some(get_vectors()[2]));
+
+        let res = VecSpace::from_raw_parts(3, vec![1.0, 2.0], vec!["a".to_string()], None);
+        assert_eq!(res.unwrap_err(), Error::LengthMismatch(2, 3));
+    }
+
+    #[test]
+    fn test_extend_from() {
+        let mut space = get_space().with_termmap();
+        let mut other = VecSpace::new(3);
+        other.extend([
+            Vector::new(&[1.0, 2.0, 3.0], "x"),
+            Vector::new(&[4.0, 5.0, 6.0], "y"),
+            Vector::new(&[7.0, 8.0, 9.0], "z"),
+        ]);
+
+        space.extend_from(&other).unwrap();
+        assert_eq!(space.len(), 6);
+        assert_eq!(space.get(0), Some(get_vectors()[0]));
+        assert_eq!(space.get(4), other.get(1));
+        assert_eq!(space.find_term("z"), other.get(2));
+        assert_eq!(space.find_term("a"), Some(get_vectors()[0]));
+
+        let res = space.extend_from(&VecSpace::new(2));
+        assert_eq!(res, Err(Error::DimMismatch(2, 3)));
+    }
+
+    #[test]
+    fn test_similarity() {
+        let space = get_space();
+        let vecs = get_vectors();
+
+        assert_eq!(space.similarity("a", "b"), Some(vecs[0].cosine(&vecs[1])));
+        assert_eq!(space.similarity("a", "x"), None);
+        assert_eq!(space.similarity("x", "a"), None);
+
+        let dist = space.distance("a", "c", Metric::Euclidean).unwrap();
+        assert_eq!(dist, Metric::Euclidean.distance(&vecs[0], &vecs[2]));
+        assert_eq!(space.distance("a", "x", Metric::Cosine), None);
+    }
+
+    #[test]
+    fn test_space_find() {
+        // test indexing after inserting.
+        let space = get_space().with_termmap();
+        let vectors = get_vectors();
+
+        for exp_vec in vectors {
+            let vec = space.find_term(exp_vec.term()).unwrap();
+            assert_eq!(vec, exp_vec);
+        }
+
+        // test indexing while inserting.
+        let mut space = VecSpace::new(3).with_termmap();
+        let vectors = get_vectors();
+        space.extend(vectors);
+        for exp_vec in vectors {
+            let vec = space.find_term(exp_vec.term()).unwrap();
+            assert_eq!(vec, exp_vec);
+        }
+    }
+
+    #[test]
+    fn test_terms_sorted() {
+        let mut space = VecSpace::new(3);
+        space.extend([
+            Vector::new(&[1.0, 0.07, 23.1], "c"),
+            Vector::new(&[0.13, 3.19, 3.12], "a"),
+            Vector::new(&[3.193, 3.1, 32.1], "b"),
+            Vector::new(&[3.193, 3.1, 32.1], "a"),
+        ]);
+
+        let exp = ["a", "b", "c"];
+        assert!(space.terms_sorted().eq(exp));
+
+        let space = space.with_sorted_termmap();
+        assert!(space.terms_sorted().eq(exp));
+        assert_eq!(space.find_term("b").unwrap().term(), "b");
+    }
+
+    #[test]
+    fn test_postprocess() {
+        // Pairs mirrored around the centroid have unit length in opposite directions after
+        // post-processing, so the resulting centroid is zero.
+        let mut space = VecSpace::new(3);
+        space.extend([
+            Vector::new(&[3.0, 1.5, 2.0], "a"),
+            Vector::new(&[-1.0, 0.5, 2.0], "b"),
+            Vector::new(&[1.0, 4.0, -1.0], "c"),
+            Vector::new(&[1.0, -2.0, 5.0], "d"),
+        ]);
+
+        space.postprocess();
+
+        for vec in space.iter() {
+            assert!((vec.length() - 1.0).abs() < 1e-5);
+        }
+
+        for c in space.centroid_data() {
+            assert!(c.abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_best_analogy() {
+        let mut space = VecSpace::new(3).with_termmap();
+        space.extend([
+            Vector::new(&[1.0, 0.0, 0.0], "man"),
+            Vector::new(&[1.0, 1.0, 0.0], "woman"),
+            Vector::new(&[1.0, 0.0, 1.0], "king"),
+            Vector::new(&[1.0, 1.0, 1.0], "queen"),
+            Vector::new(&[0.0, 0.0, -1.0], "apple"),
+        ]);
+
+        let (score, vec) = space.best_analogy("man", "woman", "king").unwrap();
+        assert_eq!(vec.term(), "queen");
+        assert!((score - 1.0).abs() < 1e-5);
+
+        assert_eq!(space.best_analogy("man", "woman", "prince"), None);
+
+        // Unindexed spaces and NaN similarities
+        let mut space = VecSpace::new(3);
+        space.extend([
+            Vector::new(&[1.0, 0.0, 0.0], "man"),
+            Vector::new(&[1.0, 1.0, 0.0], "woman"),
+            Vector::new(&[1.0, 0.0, 1.0], "king"),
+            Vector::new(&[f32::NAN, 1.0, 1.0], "broken"),
+            Vector::new(&[1.0, 1.0, 1.0], "queen"),
+        ]);
+        let (_, vec) = space.best_analogy("man", "woman", "king").unwrap();
+        assert_eq!(vec.term(), "queen");
+    }
+
+    #[test]
+    fn test_extend_term_map() {
+        let mut space = get_space().with_termmap();
+        space.extend([
+            Vector::new(&[0.5, 1.0, 2.0], "d"),
+            Vector::new(&[1.5, 2.0, 3.0], "e"),
+        ]);
+
+        let mut rebuilt = space.clone();
+        rebuilt.index_terms();
+        assert_eq!(space.term_map, rebuilt.term_map);
+        assert_eq!(space.term_map.as_ref().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_dim_stats() {
+        let space = get_space();
+        let stats = space.dim_stats();
+        assert_eq!(stats.len(), 3);
+
+        // Second dimension: 0.07, 3.19, 3.1
+        let mean = (0.07 + 3.19 + 3.1) / 3.0;
+        let var = [0.07f32, 3.19, 3.1]
+            .iter()
+            .map(|i| (i - mean).powi(2))
+            .sum::<f32>()
+            / 3.0;
+
+        let stat = stats[1];
+        assert_eq!(stat.min, 0.07);
+        assert_eq!(stat.max, 3.19);
+        assert!((stat.mean - mean).abs() < 1e-5);
+        assert!((stat.std - var.sqrt()).abs() < 1e-5);
+
+        assert!(VecSpace::new(3).dim_stats().is_empty());
+    }
+
+    #[test]
+    fn test_track_stats() {
+        let mut space = VecSpace::new(3).track_stats();
+        assert_eq!(space.dim_bounds(), None);
+        space.extend(get_vectors());
+
+        let exp: Vec<_> = get_space()
+            .dim_stats()
+            .iter()
+            .map(|i| (i.min, i.max))
+            .collect();
+        assert_eq!(space.dim_bounds(), Some(exp.as_slice()));
+        assert_eq!(get_space().track_stats().dim_bounds(), Some(exp.as_slice()));
+        assert_eq!(get_space().dim_bounds(), None);
+
+        space.minmax_normalize();
+        assert_eq!(space.dim_bounds(), Some([(0.0, 1.0); 3].as_slice()));
+        assert_eq!(space.get(0).unwrap().data()[1], 0.0);
+        assert_eq!(space.get(1).unwrap().data()[1], 1.0);
+
+        let mut untracked = get_space();
+        untracked.minmax_normalize();
+        assert_eq!(untracked.get_owned(2), space.get_owned(2));
+    }
+
+    #[test]
+    fn test_track_stats_after_modification() {
+        let batch_bounds = |space: &VecSpace| -> Vec<(f32, f32)> {
+            space.dim_stats().iter().map(|i| (i.min, i.max)).collect()
+        };
+
+        let mut space = get_space().track_stats();
+        space.clamp_all(0.0, 5.0);
+        assert_eq!(space.dim_bounds().unwrap(), batch_bounds(&space));
+
+        space.insert(Vector::new(&[0.0, 0.0, 0.0], "zero")).unwrap();
+        space.remove_zero_vectors(1e-6);
+        assert_eq!(space.dim_bounds().unwrap(), batch_bounds(&space));
+
+        space.minmax_normalize();
+        assert!(space
+            .iter()
+            .flat_map(|v| v.data().to_vec())
+            .all(|i| (0.0..=1.0).contains(&i)));
+        assert_eq!(space.dim_bounds().unwrap(), batch_bounds(&space));
+    }
+
+    #[test]
+    fn test_norm_stats() {
+        let space = get_space();
+        let stats = space.norm_stats();
+
+        let norms: Vec<f32> = get_vectors().iter().map(|i| i.length()).collect();
+        let mean = norms.iter().sum::<f32>() / 3.0;
+        assert!((stats.mean - mean).abs() < 1e-5);
+        assert_eq!(stats.min, norms[1]);
+        assert_eq!(stats.max, norms[2]);
+        assert_eq!(stats.median, norms[0]);
+
+        assert_eq!(VecSpace::new(3).norm_stats(), NormStats::default());
+    }
+
+    #[test]
+    fn test_space_owned() {
+        let space = get_space().with_termmap();
+
+        for (pos, vec) in space.iter().enumerate() {
+            assert_eq!(space.get_owned(pos).unwrap().as_ref(), vec);
+            assert_eq!(space.find_term_owned(vec.term()).unwrap().as_ref(), vec);
+        }
+
+        assert_eq!(space.get_owned(space.len()), None);
+        assert_eq!(space.find_term_owned("d"), None);
+    }
+
+    #[test]
+    fn test_kth_nearest_score() {
+        let space = get_space();
+        let query = Vector::new(&[1.0, 1.0, 1.0], "q");
+
+        for k in 1..=space.len() {
+            let exp = space.top_k(k, |v| v.cosine(&query)).last().unwrap().0;
+            assert_eq!(space.kth_nearest_score(k, |v| v.cosine(&query)), Some(exp));
+        }
+
+        assert_eq!(space.kth_nearest_score(0, |v| v.cosine(&query)), None);
+        assert_eq!(space.kth_nearest_score(4, |v| v.cosine(&query)), None);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_random() {
+        let space = VecSpace::random(8, 100, 42);
+        assert_eq!(space.len(), 100);
+        assert_eq!(space.dim(), 8);
+        assert_eq!(space.get(99).unwrap().term(), "w99");
+        assert_eq!(space, VecSpace::random(8, 100, 42));
+        assert_ne!(space, VecSpace::random(8, 100, 43));
+
+        let stats = space.dim_stats();
+        assert!(stats.iter().all(|i| i.mean.abs() < 0.5));
+        assert!(stats.iter().all(|i| (i.std - 1.0).abs() < 0.5));
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_sample_pairs() {
+        let space = get_space();
+
+        let pairs = space.sample_pairs(10, 42);
+        assert_eq!(pairs.len(), 10);
+        assert_eq!(pairs, space.sample_pairs(10, 42));
+
+        for (a, b, sim) in pairs {
+            let exp = space.get(a).unwrap().cosine(&space.get(b).unwrap());
+            assert_eq!(sim, exp);
+        }
+
+        assert!(VecSpace::new(3).sample_pairs(10, 42).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_shuffle() {
+        let mut space = get_space().with_termmap();
+        space.extend([
+            Vector::new(&[0.5, 1.0, 2.0], "d"),
+            Vector::new(&[1.5, 2.0, 3.0], "e"),
+        ]);
+        let orig = space.clone();
+
+        let mut shuffled = space.clone();
+        shuffled.shuffle(42);
+        space.shuffle(42);
+        assert_eq!(space, shuffled);
+
+        assert_eq!(space.len(), orig.len());
+        assert!(space.terms_sorted().eq(orig.terms_sorted()));
+        for vec in orig.iter() {
+            assert_eq!(space.find_term(vec.term()).unwrap(), vec);
+        }
+    }
+
+    #[test]
+    fn test_sorted_index() {
+        let mut space = get_space();
+        space.extend([
+            Vector::new(&[0.5, 1.0, 2.0], "b"),
+            Vector::new(&[1.5, 2.0, 3.0], "aa"),
+        ]);
+
+        let mapped = space.clone().with_termmap();
+        let mut sorted = space.clone().with_sorted_index();
+        assert!(sorted.term_map.is_none());
+
+        for term in ["a", "aa", "b", "c", "d", ""] {
+            assert_eq!(sorted.find_term(term), mapped.find_term(term));
+        }
+
+        // Index gets updated while inserting.
+        let mut mapped = mapped;
+        let new = [
+            Vector::new(&[3.0, 2.0, 1.0], "a"),
+            Vector::new(&[3.0, 2.0, 1.0], "0"),
+        ];
+        mapped.extend(new);
+        sorted.extend(new);
+        assert_eq!(sorted.find_term("a").unwrap().data(), &[3.0, 2.0, 1.0]);
+        for term in mapped.terms() {
+            assert_eq!(sorted.find_term(term), mapped.find_term(term));
+        }
+    }
+
+    #[test]
+    fn test_most_similar_above() {
+        let space = get_space();
+        let query = Vector::new(&[1.0, 0.1, 20.0], "q");
+
+        let all = space.top_k(3, |v| v.cosine(&query));
+        assert_eq!(space.most_similar_above(&query, 3, -1.0), all);
+
+        let min_sim = all[1].0;
+        let res = space.most_similar_above(&query, 3, min_sim);
+        assert_eq!(res, all[..2]);
+        assert!(res.iter().all(|(s, _)| *s >= min_sim));
+
+        assert!(space.most_similar_above(&query, 3, 1.1).is_empty());
+    }
+
+    #[test]
+    fn test_remove_direction() {
+        let mut space = get_space();
+        let direction = Vector::new(&[1.0, 2.0, -0.5], "dir");
+
+        space.remove_direction(&direction).unwrap();
+
+        let dir_len = direction.length();
+        for vec in space.iter() {
+            assert!((vec.dot(&direction) / dir_len).abs() < 1e-4);
+        }
+
+        let res = space.remove_direction(&Vector::new(&[1.0, 2.0], "dir"));
+        assert_eq!(res, Err(Error::DimMismatch(2, 3)));
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut space = get_space();
+        space.extend([
+            Vector::new(&[0.5, 1.0, 2.0], "d"),
+            Vector::new(&[1.5, 2.0, 3.0], "e"),
+        ]);
+
+        let chunks: Vec<_> = space.chunks(2).collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2].0.len(), 1);
+
+        let mut pos = 0;
+        for (terms, data) in chunks {
+            assert_eq!(data.len(), terms.len() * space.dim());
+            for (term, vec) in terms.iter().zip(data.chunks(space.dim())) {
+                assert_eq!(space.get(pos).unwrap(), Vector::new(vec, term));
+                pos += 1;
+            }
+        }
+        assert_eq!(pos, space.len());
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn test_ndarray() {
+        let space = get_space();
+
+        let arr = space.to_ndarray();
+        assert_eq!(arr.shape(), &[3, 3]);
+        for (row, vec) in arr.rows().into_iter().zip(space.iter()) {
+            assert_eq!(row.to_vec(), vec.data());
+        }
+
+        let terms: Vec<_> = space.terms().cloned().collect();
+        let parsed = VecSpace::from_ndarray(arr.view(), terms.clone()).unwrap();
+        assert_eq!(parsed, space);
+
+        // Non standard memory layout.
+        let transposed = arr.t().to_owned();
+        let parsed = VecSpace::from_ndarray(transposed.t(), terms).unwrap();
+        assert_eq!(parsed, space);
+
+        let res = VecSpace::from_ndarray(arr.view(), vec![]);
+        assert_eq!(res, Err(Error::LengthMismatch(0, 3)));
+    }
+
+    #[test]
+    fn test_smooth() {
+        let mut space = VecSpace::new(2);
+        space.extend([
+            Vector::new(&[1.0, 0.0], "a"),
+            Vector::new(&[1.0, 1.0], "b"),
+            Vector::new(&[-1.0, 0.1], "c"),
+        ]);
+        let orig = space.clone();
+
+        space.smooth(1, 0.5);
+
+        // Each vector moves towards its nearest neighbor and gets more similar to it.
+        let a = space.get(0).unwrap();
+        let b = orig.get(1).unwrap();
+        assert_eq!(a.data(), &[1.0, 0.5]);
+        assert!(a.cosine(&b) > orig.get(0).unwrap().cosine(&b));
+
+        // The nearest neighbor of b is a, using the original data of a.
+        assert_eq!(space.get(1).unwrap().data(), &[1.0, 0.5]);
+
+        // c has no neighbor with a positive similarity.
+        assert_eq!(space.get(2), orig.get(2));
+    }
+
+    #[test]
+    fn test_try_extend() {
+        let mut space = VecSpace::new(3);
+        let n = space.try_extend(get_vectors()).unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(space, get_space());
+
+        let mut space = VecSpace::new(3);
+        let res = space.try_extend([
+            Vector::new(&[1.0, 0.07, 23.1], "a"),
+            Vector::new(&[0.13, 3.19, 3.12], "b"),
+            Vector::new(&[3.193, 3.1], "c"),
+            Vector::new(&[3.193, 3.1, 32.1], "d"),
+        ]);
+        assert_eq!(res, Err(Error::DimMismatch(2, 3)));
+        assert_eq!(space.len(), 2);
+    }
+
+    #[test]
+    fn test_apply_matrix() {
+        let mut space = get_space();
+
+        #[rustfmt::skip]
+        let matrix = [
+            1.0, 0.0, 0.0,
+            0.0, 0.0, 2.0,
+        ];
+        space.apply_matrix(&matrix, 2).unwrap();
+
+        assert_eq!(space.dim(), 2);
+        assert_eq!(space.len(), 3);
+        for (vec, orig) in space.iter().zip(get_vectors()) {
+            assert_eq!(vec.term(), orig.term());
+            assert_eq!(vec.data(), &[orig.data()[0], orig.data()[2] * 2.0]);
+        }
+
+        let res = space.apply_matrix(&matrix, 2);
+        assert_eq!(res, Err(Error::LengthMismatch(6, 4)));
+    }
+
+    #[test]
+    fn test_enumerate_terms() {
+        let space = get_space();
+
+        let terms: Vec<_> = space.enumerate_terms().collect();
+        assert_eq!(terms, [(0, "a"), (1, "b"), (2, "c")]);
+        for (pos, term) in terms {
+            assert_eq!(space.get(pos).unwrap().term(), term);
+        }
+    }
+
+    #[test]
+    fn test_intersect_terms() {
+        let mut space = get_space().with_termmap();
+
+        let mut other = VecSpace::new(2);
+        other.insert(Vector::new(&[1.0, 2.0], "x")).unwrap();
+        other.insert(Vector::new(&[3.0, 4.0], "b")).unwrap();
+
+        space.intersect_terms(&other);
+        assert_eq!(space.len(), 1);
+        assert_eq!(space.dim(), 3);
+        assert_eq!(space.get(0), Some(get_vectors()[1]));
+        assert_eq!(space.find_term("b"), Some(get_vectors()[1]));
+        assert_eq!(space.find_term("a"), None);
+    }
+
+    #[test]
+    fn test_remove_zero_vectors() {
+        let mut space = VecSpace::new(3).with_termmap();
+        let vecs = get_vectors();
+        space.insert(vecs[0]).unwrap();
+        space
+            .insert(Vector::new(&[0.0, 0.0, 0.0], "<unk>"))
+            .unwrap();
+        space.insert(vecs[1]).unwrap();
+
+        assert_eq!(space.remove_zero_vectors(1e-6), 1);
+        assert_eq!(space.len(), 2);
+        assert_eq!(space.get(1), Some(vecs[1]));
+        assert_eq!(space.find_term("b"), Some(vecs[1]));
+        assert_eq!(space.find_term("<unk>"), None);
+
+        assert_eq!(space.remove_zero_vectors(1e-6), 0);
+    }
+
+    #[test]
+    fn test_top_k_with_early_exit() {
+        let space = get_space();
+        let query = get_vectors()[0];
+
+        let res = space.top_k_with_early_exit(1, 0.999, |v| v.cosine(&query));
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].1, query);
+
+        // "a" is found first, but two vectors are required so "b" gets scored too and "c" not.
+        let res = space.top_k_with_early_exit(2, 0.999, |v| v.cosine(&query));
+        let terms: Vec<_> = res.iter().map(|i| i.1.term()).collect();
+        assert_eq!(terms, ["a", "b"]);
+
+        // Without reaching the target the whole space is scanned.
+        let res = space.top_k_with_early_exit(2, 2.0, |v| v.cosine(&query));
+        assert_eq!(res, space.top_k(2, |v| v.cosine(&query)));
+
+        assert!(space
+            .top_k_with_early_exit(0, 1.0, |v| v.cosine(&query))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_words() {
+        let space = get_space();
+
+        let words = space.words();
+        assert_eq!(words.len(), space.len());
+        for (i, word) in words.iter().enumerate() {
+            assert_eq!(word, space.get(i).unwrap().term());
+        }
+    }
+
+    #[test]
+    fn test_find_phrase() {
+        let mut space = get_space();
+        space.insert(Vector::new(&[1.0, 2.0, 3.0], "a_b")).unwrap();
+        let space = space.with_termmap();
+
+        let vec = space.find_phrase("a b").unwrap();
+        assert_eq!(vec.as_ref(), Vector::new(&[1.0, 2.0, 3.0], "a_b"));
+
+        let [_, b, c] = get_vectors();
+        let vec = space.find_phrase("b  c").unwrap();
+        assert_eq!(vec, b + c);
+
+        assert_eq!(space.find_phrase("a x"), None);
+        assert_eq!(space.find_phrase(""), None);
+
+        let unindexed = get_space();
+        assert_eq!(unindexed.find_phrase("b c"), Some(b + c));
+    }
+
+    #[test]
+    fn test_permute_dims() {
+        let mut space = get_space().track_stats();
+        space.permute_dims(&[2, 0, 1]).unwrap();
+
+        for (got, exp) in space.iter().zip(get_vectors()) {
+            let exp = exp.data();
+            assert_eq!(got.data(), &[exp[2], exp[0], exp[1]]);
+        }
+        let bounds = get_space().track_stats().dim_bounds().unwrap().to_vec();
+        assert_eq!(
+            space.dim_bounds(),
+            Some([bounds[2], bounds[0], bounds[1]].as_slice())
+        );
+
+        assert_eq!(space.permute_dims(&[0, 1]), Err(Error::DimMismatch(2, 3)));
+        assert_eq!(
+            space.permute_dims(&[0, 1, 1]),
+            Err(Error::InvalidPermutation)
+        );
+        assert_eq!(
+            space.permute_dims(&[0, 1, 3]),
+            Err(Error::InvalidPermutation)
+        );
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut space = VecSpace::new(3).with_termmap();
+        space.extend(get_vectors());
+        for _ in 0..1000 {
+            space.push_anonymous(&[0.0, 0.0, 0.0]).unwrap();
+        }
+        let full_cap = space.total_cap();
+
+        assert_eq!(space.remove_zero_vectors(1e-6), 1000);
+        space.compact();
+
+        assert_eq!(space, get_space().with_termmap());
+        assert!(space.total_cap() < full_cap / 10);
+        assert_eq!(space.find_term("c"), Some(get_vectors()[2]));
+    }
+
+    #[test]
+    fn test_bottom_k() {
+        let space = get_space();
+        let query = Vector::new(&[0.1, 3.0, 3.0], "q");
+
+        let mut all = space.top_k(space.len(), |v| v.cosine(&query));
+        all.reverse();
+
+        let res = space.bottom_k(2, |v| v.cosine(&query));
+        assert_eq!(res, all[..2]);
+        assert!(res[0].0 <= res[1].0);
+        assert!(space.bottom_k(0, |v| v.cosine(&query)).is_empty());
+    }
+
+    #[test]
+    fn test_top_k_unsorted() {
+        let space = get_space();
+        let query = get_vectors()[1];
+
+        let mut res = space.top_k_unsorted(2, |v| v.cosine(&query));
+        res.sort_by(|a, b| b.0.total_cmp(&a.0));
+        assert_eq!(res, space.top_k(2, |v| v.cosine(&query)));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_par_extend() {
+        use crate::vector::OwnedVector;
+
+        let vecs: Vec<_> = (0..100)
+            .map(|i| {
+                let i = i as f32;
+                OwnedVector::new_raw(vec![i, i * 2.0, i * 3.0], format!("t{i}"))
+            })
+            .collect();
+
+        let mut serial = VecSpace::new(3).with_termmap();
+        serial.extend(vecs.iter());
+
+        let mut parallel = VecSpace::new(3).with_termmap();
+        parallel.par_extend(vecs.clone()).unwrap();
+        assert_eq!(parallel, serial);
+
+        let mut space = get_space();
+        let res = space.par_extend(vec![
+            OwnedVector::new_raw(vec![1.0, 2.0, 3.0], "x".to_string()),
+            OwnedVector::new_raw(vec![1.0, 2.0], "y".to_string()),
+        ]);
+        assert_eq!(res, Err(Error::DimMismatch(2, 3)));
+        assert_eq!(space, get_space());
+    }
+
+    #[test]
+    fn test_diff() {
+        let space = get_space();
+
+        let mut other = VecSpace::new(3);
+        other.insert(get_vectors()[0]).unwrap();
+        other.insert(Vector::new(&[3.193, 3.2, 32.1], "c")).unwrap();
+        other.insert(Vector::new(&[1.0, 1.0, 1.0], "d")).unwrap();
+
+        let diff = space.diff(&other, 0.01).unwrap();
+        assert_eq!(diff.added, ["d"]);
+        assert_eq!(diff.removed, ["b"]);
+        assert_eq!(diff.changed, ["c"]);
+
+        let diff = space.diff(&other, 0.5).unwrap();
+        assert!(diff.changed.is_empty());
+
+        assert!(space.diff(&space, 0.0).unwrap().is_empty());
+
+        let other = VecSpace::new(2);
+        assert_eq!(space.diff(&other, 0.0), Err(Error::DimMismatch(2, 3)));
+    }
+
+    #[test]
+    fn test_clamp_all() {
+        let mut space = get_space();
+        space.clamp_all(0.5, 10.0);
+
+        for (vec, orig) in space.iter().zip(get_vectors()) {
+            assert_eq!(vec, orig.clamp(0.5, 10.0).as_ref());
+        }
+        assert_eq!(space.get(0).unwrap().data(), &[1.0, 0.5, 10.0]);
+    }
+
+    #[test]
+    fn test_top_k_nan() {
+        let space = get_space();
+
+        // "b" scores NaN and must neither be returned nor change the order of the others.
+        let sim = |v: &Vector| match v.term() {
+            "a" => 0.5,
+            "b" => f32::NAN,
+            _ => 0.9,
+        };
+
+        let res = space.top_k(3, sim);
+        let terms: Vec<_> = res.iter().map(|i| i.1.term()).collect();
+        assert_eq!(terms, ["c", "a"]);
+
+        let res = space.top_k(1, sim);
+        assert_eq!(res[0].1.term(), "c");
+
+        assert_eq!(space.top_k_unsorted(3, sim).len(), 2);
+
+        assert_eq!(space.kth_nearest_score(2, sim), Some(0.5));
+        assert_eq!(
+            space.kth_nearest_score(2, sim),
+            space.top_k(2, sim).last().map(|i| i.0)
+        );
+        assert_eq!(space.kth_nearest_score(3, sim), None);
+    }
+
+    #[test]
+    fn test_push_anonymous() {
+        let mut space = get_space().with_termmap();
+
+        assert_eq!(space.push_anonymous(&[1.0, 2.0, 3.0]), Ok(3));
+        assert_eq!(space.push_anonymous(&[4.0, 5.0, 6.0]), Ok(4));
+        assert_eq!(
+            space.push_anonymous(&[1.0, 2.0]),
+            Err(Error::DimMismatch(2, 3))
+        );
+
+        assert_eq!(space.len(), 5);
+        assert_eq!(
+            space.find_term("#3"),
+            Some(Vector::new(&[1.0, 2.0, 3.0], "#3"))
+        );
+        assert_eq!(space.find_term("#4").unwrap().data(), &[4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn test_map_terms() {
+        let mut space = get_space().with_termmap();
+        space.map_terms(|t| t.to_uppercase());
+
+        let vectors = get_vectors();
+        assert_eq!(space.len(), 3);
+        assert_eq!(space.find_term("a"), None);
+        assert_eq!(space.find_term("B").unwrap().data(), vectors[1].data());
+
+        // "B" and "C" collide, so only "B" is kept.
+        space.map_terms(|t| if t == "A" { t.into() } else { "B".into() });
+        assert_eq!(space.len(), 2);
+        assert_eq!(space.find_term("B").unwrap().data(), vectors[1].data());
+        assert_eq!(space.find_term("A").unwrap().data(), vectors[0].data());
+    }
+
+    #[test]
+    fn test_index_id_overflow() {
+        assert_eq!(super::index_id(0), Ok(0));
+        assert_eq!(super::index_id(u32::MAX as usize), Ok(u32::MAX));
+        assert_eq!(
+            super::index_id(u32::MAX as usize + 1),
+            Err(Error::IndexOverflow)
+        );
+    }
+
+    #[test]
+    fn test_average_spaces() {
+        let mut a = VecSpace::new(2);
+        a.insert(Vector::new(&[1.0, 2.0], "x")).unwrap();
+        a.insert(Vector::new(&[4.0, 4.0], "y")).unwrap();
+
+        let mut b = VecSpace::new(2);
+        b.insert(Vector::new(&[3.0, 0.0], "y")).unwrap();
+        b.insert(Vector::new(&[5.0, 6.0], "z")).unwrap();
+
+        let avg = VecSpace::average_spaces(&[a.clone(), b]).unwrap();
+        assert_eq!(avg.len(), 3);
+        assert_eq!(avg.get(0), Some(Vector::new(&[1.0, 2.0], "x")));
+        assert_eq!(avg.get(1), Some(Vector::new(&[3.5, 2.0], "y")));
+        assert_eq!(avg.get(2), Some(Vector::new(&[5.0, 6.0], "z")));
+
+        let res = VecSpace::average_spaces(&[a, get_space()]);
+        assert_eq!(res, Err(Error::DimMismatch(3, 2)));
+    }
+
+    #[test]
+    fn test_dedup_similar() {
+        let mut space = get_space().with_termmap();
+        space
+            .insert(Vector::new(&[1.01, 0.07, 23.0], "a2"))
+            .unwrap();
+
+        space.dedup_similar(0.9999);
+        let terms: Vec<_> = space.terms().collect();
+        assert_eq!(terms, ["a", "b", "c"]);
+        assert_eq!(space.find_term("a2"), None);
+        assert_eq!(space, get_space().with_termmap());
+    }
+
+    #[test]
+    fn test_scored() {
+        let space = get_space();
+        let query = get_vectors()[0];
+
+        let scored: Vec<_> = space.scored(|v| v.cosine(&query)).collect();
+        assert_eq!(scored.len(), space.len());
+        for ((score, vec), orig) in scored.into_iter().zip(get_vectors()) {
+            assert_eq!(vec, orig);
+            assert_eq!(score, orig.cosine(&query));
+        }
+    }
+
+    #[test]
+    fn test_centrality() {
+        let mut space = VecSpace::new(2);
+        space.insert(Vector::new(&[1.0, 0.0], "x")).unwrap();
+        space.insert(Vector::new(&[0.0, 1.0], "y")).unwrap();
+        space.insert(Vector::new(&[1.0, 1.0], "xy")).unwrap();
+
+        let diag = std::f32::consts::FRAC_1_SQRT_2;
+        let exp = [diag / 2.0, diag / 2.0, diag];
+
+        let centralities = space.centralities();
+        assert_eq!(centralities.len(), 3);
+        for (id, exp) in exp.into_iter().enumerate() {
+            assert!((space.centrality(id).unwrap() - exp).abs() < 1e-6);
+            assert!((centralities[id] - exp).abs() < 1e-6);
+        }
+
+        assert_eq!(space.centrality(3), None);
+
+        let mut single = VecSpace::new(2);
+        single.insert(Vector::new(&[1.0, 0.0], "x")).unwrap();
+        assert_eq!(single.centrality(0), None);
+        assert!(single.centralities().is_empty());
+    }
+
+    #[test]
+    fn test_apply_orthogonal() {
+        let mut space = get_space();
+
+        #[rustfmt::skip]
+        let identity = [
+            1.0, 0.0, 0.0,
+            0.0, 1.0, 0.0,
+            0.0, 0.0, 1.0,
+        ];
+        space.apply_orthogonal(&identity).unwrap();
+        assert_eq!(space, get_space());
+
+        let res = space.apply_orthogonal(&[1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(res, Err(Error::LengthMismatch(4, 9)));
+
+        let mut space = VecSpace::new(2);
+        space.insert(Vector::new(&[1.0, 0.0], "x")).unwrap();
+        space.insert(Vector::new(&[1.0, 1.0], "xy")).unwrap();
+
+        // Rotation by 90 degrees counterclockwise.
+        #[rustfmt::skip]
+        let rotation = [
+            0.0, -1.0,
+            1.0, 0.0,
+        ];
+        space.apply_orthogonal(&rotation).unwrap();
+        assert_eq!(space.dim(), 2);
+        assert_eq!(space.get(0).unwrap().data(), &[0.0, 1.0]);
+        assert_eq!(space.get(1).unwrap().data(), &[-1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_top_k_cosine() {
+        let space = get_space();
+        let query = Vector::new(&[0.1, 3.0, 3.0], "q");
+
+        let res = space.top_k_cosine(3, &query);
+        let exp = space.top_k(3, |v| v.cosine(&query));
+        assert_eq!(res.len(), exp.len());
+        for (got, exp) in res.iter().zip(&exp) {
+            assert_eq!(got.1, exp.1);
+            assert!((got.0 - exp.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let space = get_space();
+        let query = Vector::new(&[0.1, 3.0, 3.0], "q");
+
+        let hits = space.search(2, |v| v.cosine(&query));
+        let exp = space.top_k(2, |v| v.cosine(&query));
+        assert_eq!(hits.len(), 2);
+        for (hit, exp) in hits.iter().zip(&exp) {
+            assert_eq!(hit.score(), exp.0);
+            assert_eq!(hit.vector(), exp.1);
+            assert_eq!(hit.term(), exp.1.term());
+        }
+    }
+
+    #[test]
+    fn test_write_terms() {
+        let space = get_space();
+
+        let mut buf = vec![];
+        let n = space.write_terms(&mut buf).unwrap();
+        assert_eq!(n, buf.len());
+        assert_eq!(buf, b"a\nb\nc\n");
+    }
+
+    #[test]
+    fn test_anchor_embedding() {
+        let space = get_space();
+        let anchors = [
+            Vector::new(&[1.0, 0.0, 0.0], "x"),
+            Vector::new(&[0.0, 0.0, 1.0], "z"),
+        ];
+
+        let emb = space.anchor_embedding(&anchors).unwrap();
+        assert_eq!(emb.len(), space.len());
+        for (v, sims) in space.iter().zip(&emb) {
+            assert_eq!(sims.len(), 2);
+            assert!((sims[0] - v.cosine(&anchors[0])).abs() < 1e-6);
+            assert!((sims[1] - v.cosine(&anchors[1])).abs() < 1e-6);
+        }
+
+        let res = space.anchor_embedding(&[Vector::new(&[1.0, 0.0], "x")]);
+        assert_eq!(res, Err(Error::DimMismatch(2, 3)));
+    }
+
+    #[test]
+    fn test_knn_graph() {
+        let space = get_space();
+
+        let graph = space.knn_graph(2);
+        assert_eq!(graph.len(), 3);
+        for (id, neighbors) in graph.iter().enumerate() {
+            assert_eq!(neighbors.len(), 2);
+            assert!(neighbors.iter().all(|(n, _)| *n != id));
+            assert!(neighbors[0].1 >= neighbors[1].1);
+
+            let vec = space.get(id).unwrap();
+            for (n, sim) in neighbors {
+                let exp = vec.cosine(&space.get(*n).unwrap());
+                assert!((sim - exp).abs() < 1e-6);
+            }
+        }
+
+        // "a" and "c" point into similar directions.
+        assert_eq!(graph[0][0].0, 2);
+
+        assert!(space.knn_graph(5).iter().all(|i| i.len() == 2));
+        assert!(space.knn_graph(0).iter().all(|i| i.is_empty()));
+    }
+
+    #[test]
+    fn test_round_components() {
+        let mut space = get_space();
+        space.round_components(1);
+        assert_eq!(space.get(0).unwrap().data(), &[1.0, 0.1, 23.1]);
+        assert_eq!(space.get(1).unwrap().data(), &[0.1, 3.2, 3.1]);
+        assert_eq!(space.get(2).unwrap().data(), &[3.2, 3.1, 32.1]);
+
+        space.round_components(0);
+        assert_eq!(space.get(1).unwrap().data(), &[0.0, 3.0, 3.0]);
+
+        let mut space = VecSpace::new(3);
+        space.extend([Vector::new(&[f32::MAX, -3.0e37, 0.123], "a")]);
+        let orig = space.clone();
+        space.round_components(50);
+        assert_eq!(space, orig);
+
+        space.round_components(2);
+        assert_eq!(space.get(0).unwrap().data(), &[f32::MAX, -3.0e37, 0.12]);
+
+        let mut space = VecSpace::new(2);
+        space.extend([Vector::new(&[1.0e30, -16_777_217.0], "a")]);
+        let orig = space.clone();
+        for decimals in 0..9 {
+            space.round_components(decimals);
+            assert_eq!(space, orig);
+        }
+    }
+
+    #[test]
+    fn test_dedup_terms() {
+        let vecs = [
+            Vector::new(&[1.2, 2.0, 4.4], "term1"),
+            Vector::new(&[2.3, 1.0, 3.4], "term3"),
+            Vector::new(&[3.1, 9.4, 3.0], "term3"),
+        ];
+        let mut space = VecSpace::new(3).with_termmap();
+        space.extend(vecs);
+
+        let dedup = |policy| {
+            let mut space = space.clone();
+            space.dedup_terms(policy);
+            assert_eq!(space.len(), 2);
+            assert_eq!(space.get(0), Some(vecs[0]));
+            space.find_term_owned("term3").unwrap()
+        };
+
+        assert_eq!(dedup(DedupPolicy::KeepFirst).as_ref(), vecs[1]);
+        assert_eq!(dedup(DedupPolicy::KeepLast).as_ref(), vecs[2]);
+
+        let avg = dedup(DedupPolicy::Average);
+        let exp = Vector::new(&[2.7, 5.2, 3.2], "term3");
+        assert!(avg.approx_eq(&exp, 1e-6));
+    }
+
+    #[test]
+    fn test_term_index() {
+        let mut space = get_space();
+        space.insert(Vector::new(&[1.0, 2.0, 3.0], "b")).unwrap();
+
+        for space in [
+            space.clone(),
+            space.clone().with_termmap(),
+            space.with_sorted_index(),
+        ] {
+            assert_eq!(space.term_index("a"), Some(0));
+            assert_eq!(space.term_index("c"), Some(2));
+            assert_eq!(space.term_index("b"), Some(3));
+            assert_eq!(space.term_index("x"), None);
+
+            let idx = space.term_index("c").unwrap();
+            assert_eq!(space.get(idx).unwrap().term(), "c");
+        }
+    }
+
+    #[test]
+    fn test_swap() {
+        let vectors = get_vectors();
+
+        for mut space in [get_space().with_termmap(), get_space().with_sorted_index()] {
+            space.swap(0, 2);
+
+            assert_eq!(space.get(0), Some(vectors[2]));
+            assert_eq!(space.get(1), Some(vectors[1]));
+            assert_eq!(space.get(2), Some(vectors[0]));
+            for vec in vectors {
+                assert_eq!(space.find_term(vec.term()), Some(vec));
+            }
+
+            space.swap(1, 1);
+            assert_eq!(space.get(1), Some(vectors[1]));
+
+            // Duplicate terms keep resolving to the vector with the highest ID.
+            space.insert(Vector::new(&[1.0, 2.0, 3.0], "b")).unwrap();
+            space.swap(1, 3);
+            assert_eq!(space.find_term("b"), Some(vectors[1]));
+        }
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut space = get_space().with_termmap();
+        space.add_declared_len(3);
+        space.clear();
+
+        assert!(space.is_empty());
+        assert_eq!(space.declared_len(), None);
+        assert_eq!(space.find_term("a"), None);
+    }
+
+    #[test]
+    fn test_swap_duplicates() {
+        let vecs = [
+            Vector::new(&[1.0, 0.0, 0.0], "a"),
+            Vector::new(&[2.0, 0.0, 0.0], "b"),
+            Vector::new(&[3.0, 0.0, 0.0], "a"),
+            Vector::new(&[4.0, 0.0, 0.0], "c"),
+            Vector::new(&[5.0, 0.0, 0.0], "a"),
+            Vector::new(&[6.0, 0.0, 0.0], "b"),
+        ];
+
+        for indexed in [
+            VecSpace::new(3).with_termmap(),
+            VecSpace::new(3).with_sorted_index(),
+        ] {
+            let mut space = indexed.clone();
+            space.extend(vecs);
+
+            for (a, b) in [(0, 4), (4, 1), (2, 3), (5, 0), (3, 4), (1, 2), (0, 5)] {
+                space.swap(a, b);
+
+                // Must match a space indexed from scratch.
+                let mut exp = indexed.clone();
+                exp.extend(space.iter());
+                assert_eq!(space, exp);
+            }
         }
     }
 }
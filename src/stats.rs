@@ -0,0 +1,19 @@
+/// Statistics of a single dimension over all vectors in a [`VecSpace`](crate::space::VecSpace).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DimStat {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+
+    /// Population standard deviation.
+    pub std: f32,
+}
+
+/// Statistics of the L2 norms of all vectors in a [`VecSpace`](crate::space::VecSpace).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NormStats {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub median: f32,
+}
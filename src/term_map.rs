@@ -0,0 +1,88 @@
+use ahash::AHashMap;
+use std::collections::BTreeMap;
+
+/// Index for terms to their vector ID in a [`VecSpace`](crate::space::VecSpace).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TermMap {
+    /// Hash based index. Fastest lookups but terms are not ordered.
+    Hash(AHashMap<String, u32>),
+
+    /// Tree based index which keeps the terms sorted.
+    BTree(BTreeMap<String, u32>),
+}
+
+impl TermMap {
+    /// Returns the vector ID of the given term.
+    #[inline]
+    pub fn get(&self, term: &str) -> Option<u32> {
+        match self {
+            TermMap::Hash(map) => map.get(term).copied(),
+            TermMap::BTree(map) => map.get(term).copied(),
+        }
+    }
+
+    /// Maps `term` to the vector ID `id`.
+    #[inline]
+    pub fn insert(&mut self, term: String, id: u32) {
+        match self {
+            TermMap::Hash(map) => map.insert(term, id),
+            TermMap::BTree(map) => map.insert(term, id),
+        };
+    }
+
+    /// Amount of indexed terms.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            TermMap::Hash(map) => map.len(),
+            TermMap::BTree(map) => map.len(),
+        }
+    }
+
+    /// Returns `true` if no term is indexed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all terms from the index.
+    pub fn clear(&mut self) {
+        match self {
+            TermMap::Hash(map) => map.clear(),
+            TermMap::BTree(map) => map.clear(),
+        }
+    }
+
+    /// Returns the amount of terms the index can hold without allocating. The sorted index
+    /// allocates each node separately and has no spare capacity, so its amount of indexed terms
+    /// is returned instead.
+    pub fn capacity(&self) -> usize {
+        match self {
+            TermMap::Hash(map) => map.capacity(),
+            TermMap::BTree(map) => map.len(),
+        }
+    }
+
+    /// Converts the index into a hash map. Sorted terms get moved into a new hash map.
+    pub fn into_hash_map(self) -> AHashMap<String, u32> {
+        match self {
+            TermMap::Hash(map) => map,
+            TermMap::BTree(map) => map.into_iter().collect(),
+        }
+    }
+
+    /// Shrinks the capacity of the index as much as possible.
+    pub fn shrink_to_fit(&mut self) {
+        if let TermMap::Hash(map) = self {
+            map.shrink_to_fit();
+        }
+    }
+}
+
+impl Default for TermMap {
+    #[inline]
+    fn default() -> Self {
+        Self::Hash(AHashMap::new())
+    }
+}
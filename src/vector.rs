@@ -1,4 +1,4 @@
-use crate::as_vector::AsVectorRef;
+use crate::{as_vector::AsVectorRef, error::Error};
 use borrowme::borrowme;
 use nalgebra::DVectorView;
 use std::ops::Add;
@@ -37,6 +37,10 @@ impl<'v, 't> Vector<'v, 't> {
     }
 
     /// Calculates the cosine similarity between two words.
+    ///
+    /// Vectors with different dimensions are not detected: only the overlapping components are
+    /// used, resulting in a misleading similarity. Use [`Vector::checked_cosine`] if the
+    /// dimensions might differ.
     pub fn cosine<'v2, 't2, R>(&self, other: &R) -> f32
     where
         R: AsVectorRef<'v2, 't2>,
@@ -56,6 +60,59 @@ impl<'v, 't> Vector<'v, 't> {
         dot / div
     }
 
+    /// Calculates the cosine similarity between two words or returns [`Error::DimMismatch`] if
+    /// their dimensions differ.
+    pub fn checked_cosine<'v2, 't2, R>(&self, other: &R) -> Result<f32, Error>
+    where
+        R: AsVectorRef<'v2, 't2>,
+    {
+        let other = other.as_vec_ref();
+        if other.dim() != self.dim() {
+            return Err(Error::DimMismatch(other.dim(), self.dim()));
+        }
+        Ok(self.cosine(&other))
+    }
+
+    /// Returns `true` if both vectors have the same term and dimension and each pair of components
+    /// differs by at most `epsilon`.
+    pub fn approx_eq<'v2, 't2, R>(&self, other: &R, epsilon: f32) -> bool
+    where
+        R: AsVectorRef<'v2, 't2>,
+    {
+        let other = other.as_vec_ref();
+        self.term == other.term
+            && self.dim() == other.dim()
+            && self
+                .data
+                .iter()
+                .zip(other.data)
+                .all(|(a, b)| (a - b).abs() <= epsilon)
+    }
+
+    /// Calculates the cosine similarity to each of the given candidates. The length of `self`
+    /// only gets calculated once, which is faster than calling [`Vector::cosine`] for each
+    /// candidate.
+    pub fn cosine_batch(&self, candidates: &[Vector]) -> Vec<f32> {
+        let len = self.length();
+
+        candidates
+            .iter()
+            .map(|other| {
+                let dot = self.dot(other);
+                if dot == 0.0 {
+                    return 0.0;
+                }
+
+                let div = len * other.length();
+                if div == 0.0 {
+                    return 0.0;
+                }
+
+                dot / div
+            })
+            .collect()
+    }
+
     /// Calculates the dot product of two vectors
     pub fn dot<'v2, 't2, R>(&self, other: &R) -> f32
     where
@@ -70,6 +127,73 @@ impl<'v, 't> Vector<'v, 't> {
             .sum()
     }
 
+    /// Projects the vector onto `axis`. Projecting onto a zero vector results in a zero vector.
+    /// The term of `self` is kept.
+    ///
+    /// # Panics
+    /// Panics if the dimensions of the vectors don't match.
+    pub fn project_onto<'v2, 't2, R>(&self, axis: &R) -> OwnedVector
+    where
+        R: AsVectorRef<'v2, 't2>,
+    {
+        let axis = axis.as_vec_ref();
+        assert_eq!(self.dim(), axis.dim());
+
+        let axis_dot = axis.dot(&axis);
+        let factor = if axis_dot == 0.0 {
+            0.0
+        } else {
+            self.dot(&axis) / axis_dot
+        };
+
+        let data = axis.data.iter().map(|i| i * factor).collect();
+        OwnedVector::new_raw(data, self.term.to_string())
+    }
+
+    /// Removes the component of the vector that points into the direction of `axis`, resulting in
+    /// a vector orthogonal to `axis`. The term of `self` is kept.
+    ///
+    /// # Panics
+    /// Panics if the dimensions of the vectors don't match.
+    pub fn reject_from<'v2, 't2, R>(&self, axis: &R) -> OwnedVector
+    where
+        R: AsVectorRef<'v2, 't2>,
+    {
+        let mut proj = self.project_onto(axis);
+        for (p, v) in proj.data.iter_mut().zip(self.data.iter()) {
+            *p = v - *p;
+        }
+        proj
+    }
+
+    /// Counts the dimensions in which the signs of both vectors differ.
+    ///
+    /// # Panics
+    /// Panics if the dimensions of the vectors don't match.
+    pub fn hamming<'v2, 't2, R>(&self, other: &R) -> usize
+    where
+        R: AsVectorRef<'v2, 't2>,
+    {
+        let other = other.as_vec_ref();
+        assert_eq!(self.dim(), other.dim());
+
+        self.data
+            .iter()
+            .zip(other.data.iter())
+            .filter(|(a, b)| a.signum() != b.signum())
+            .count()
+    }
+
+    /// Encodes the vector data as base64 string of its little endian bytes. The term is not
+    /// included.
+    #[cfg(feature = "base64")]
+    pub fn to_base64(&self) -> String {
+        use base64::Engine;
+
+        let bytes: Vec<u8> = self.data.iter().flat_map(|i| i.to_le_bytes()).collect();
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
     #[inline]
     pub fn vec(&self) -> DVectorView<'_, f32> {
         DVectorView::from_slice(self.data, 1)
@@ -81,19 +205,103 @@ impl<'v, 't> Vector<'v, 't> {
         // self.vec().norm()
         self.data.iter().map(|i| i.powi(2)).sum::<f32>().sqrt()
     }
+
+    /// Returns a copy of the vector with `to_dim` dimensions. Missing dimensions are filled up
+    /// with zeros and exceeding ones get truncated.
+    pub fn padded(&self, to_dim: usize) -> OwnedVector {
+        let mut data = self.data[..to_dim.min(self.dim())].to_vec();
+        data.resize(to_dim, 0.0);
+        OwnedVector::new_raw(data, self.term.to_string())
+    }
+
+    /// Returns a copy of the vector with `term` as its term.
+    #[inline]
+    pub fn with_term(&self, term: &str) -> OwnedVector {
+        OwnedVector::new(self.data, term)
+    }
+
+    /// Returns a copy of the vector with each component clamped into `[min, max]`.
+    ///
+    /// # Panics
+    /// Panics if `min > max` or either of them is NaN.
+    pub fn clamp(&self, min: f32, max: f32) -> OwnedVector {
+        let data = self.data.iter().map(|i| i.clamp(min, max)).collect();
+        OwnedVector::new_raw(data, self.term.to_string())
+    }
 }
 
 impl OwnedVector {
+    /// Creates a new vector taking ownership of `data` and `term` without copying them.
     #[inline]
     pub fn new_raw(data: Vec<f32>, term: String) -> Self {
         Self { data, term }
     }
 
+    /// Creates a new vector by copying `data` and `term`. Use [`OwnedVector::new_raw`] to avoid
+    /// the copy if the data is already owned.
     #[inline]
     pub fn new(data: &[f32], term: &str) -> Self {
         borrowme::ToOwned::to_owned(&Vector::new(data, term))
     }
 
+    /// Decodes a vector from a base64 string created with [`Vector::to_base64`].
+    #[cfg(feature = "base64")]
+    pub fn from_base64(term: String, s: &str) -> Result<Self, Error> {
+        use base64::Engine;
+
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(s)
+            .map_err(|_| Error::InvalidVectorFormat)?;
+
+        let chunks = bytes.chunks_exact(4);
+        if !chunks.remainder().is_empty() {
+            return Err(Error::InvalidVectorFormat);
+        }
+
+        let data = chunks
+            .map(|i| f32::from_le_bytes([i[0], i[1], i[2], i[3]]))
+            .collect();
+        Ok(Self::new_raw(data, term))
+    }
+
+    /// Encodes the vector data as base64 string.
+    #[cfg(feature = "base64")]
+    #[inline]
+    pub fn to_base64(&self) -> String {
+        self.as_ref().to_base64()
+    }
+
+    /// Creates a new vector by collecting the data from an iterator.
+    #[inline]
+    pub fn from_iter_with_term<I: IntoIterator<Item = f32>>(term: String, iter: I) -> Self {
+        Self::new_raw(iter.into_iter().collect(), term)
+    }
+
+    /// Calculates the component-wise geometric mean of `vectors`. The magnitude of each component
+    /// is the geometric mean of the absolute values and its sign is the sign of their sum. The
+    /// term is made of all terms separated by spaces. Returns `None` if `vectors` is empty.
+    ///
+    /// # Panics
+    /// Panics if the vectors have different dimensions.
+    pub fn geometric_mean(vectors: &[Vector]) -> Option<OwnedVector> {
+        let first = vectors.first()?;
+        let dim = first.dim();
+        assert!(vectors.iter().all(|i| i.dim() == dim));
+
+        let n = vectors.len() as f32;
+        let data = (0..dim)
+            .map(|c| {
+                let sum: f32 = vectors.iter().map(|v| v.data[c]).sum();
+                // Summing logarithms avoids overflows of the product.
+                let log_sum: f32 = vectors.iter().map(|v| v.data[c].abs().ln()).sum();
+                (log_sum / n).exp().copysign(sum)
+            })
+            .collect();
+
+        let term = vectors.iter().map(|i| i.term).collect::<Vec<_>>().join(" ");
+        Some(OwnedVector::new_raw(data, term))
+    }
+
     /// Returns a reference to the data of the owned vector.
     #[inline]
     pub fn as_ref(&self) -> Vector {
@@ -135,6 +343,69 @@ impl OwnedVector {
     {
         self.as_ref().cosine(other)
     }
+
+    /// Calculates the cosine similarity between two vectors or returns [`Error::DimMismatch`] if
+    /// their dimensions differ.
+    #[inline]
+    pub fn checked_cosine<'v2, 't2, R>(&self, other: &R) -> Result<f32, Error>
+    where
+        R: AsVectorRef<'v2, 't2>,
+    {
+        self.as_ref().checked_cosine(other)
+    }
+
+    /// Projects the vector onto `axis`.
+    #[inline]
+    pub fn project_onto<'v, 't, R: AsVectorRef<'v, 't>>(&self, axis: &R) -> OwnedVector {
+        self.as_ref().project_onto(axis)
+    }
+
+    /// Removes the component of the vector that points into the direction of `axis`.
+    #[inline]
+    pub fn reject_from<'v, 't, R: AsVectorRef<'v, 't>>(&self, axis: &R) -> OwnedVector {
+        self.as_ref().reject_from(axis)
+    }
+
+    /// Counts the dimensions in which the signs of both vectors differ.
+    #[inline]
+    pub fn hamming<'v, 't, R: AsVectorRef<'v, 't>>(&self, other: &R) -> usize {
+        self.as_ref().hamming(other)
+    }
+
+    /// Calculates the cosine similarity to each of the given candidates.
+    #[inline]
+    pub fn cosine_batch(&self, candidates: &[Vector]) -> Vec<f32> {
+        self.as_ref().cosine_batch(candidates)
+    }
+
+    /// Returns a copy of the vector with `to_dim` dimensions. Missing dimensions are filled up
+    /// with zeros and exceeding ones get truncated.
+    #[inline]
+    pub fn padded(&self, to_dim: usize) -> OwnedVector {
+        self.as_ref().padded(to_dim)
+    }
+
+    /// Returns a copy of the vector with `term` as its term.
+    #[inline]
+    pub fn with_term(&self, term: &str) -> OwnedVector {
+        self.as_ref().with_term(term)
+    }
+
+    /// Returns `true` if both vectors have the same term and dimension and each pair of components
+    /// differs by at most `epsilon`.
+    #[inline]
+    pub fn approx_eq<'v2, 't2, R>(&self, other: &R, epsilon: f32) -> bool
+    where
+        R: AsVectorRef<'v2, 't2>,
+    {
+        self.as_ref().approx_eq(other, epsilon)
+    }
+
+    /// Returns a copy of the vector with each component clamped into `[min, max]`.
+    #[inline]
+    pub fn clamp(&self, min: f32, max: f32) -> OwnedVector {
+        self.as_ref().clamp(min, max)
+    }
 }
 
 impl<'v, 't, 'v2, 't2, T> Add<T> for Vector<'v, 't>
@@ -169,6 +440,22 @@ where
     }
 }
 
+/// Creates a vector from a term and its data without copying them.
+impl From<(String, Vec<f32>)> for OwnedVector {
+    #[inline]
+    fn from((term, data): (String, Vec<f32>)) -> Self {
+        OwnedVector::new_raw(data, term)
+    }
+}
+
+/// Collects the data into a vector with an empty term.
+impl FromIterator<f32> for OwnedVector {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = f32>>(iter: T) -> Self {
+        OwnedVector::from_iter_with_term(String::new(), iter)
+    }
+}
+
 impl<'v, 't> AsVectorRef<'v, 't> for &Vector<'v, 't> {
     #[inline]
     fn as_vec_ref(&self) -> Vector<'v, 't> {
@@ -189,3 +476,172 @@ impl<'a> AsVectorRef<'a, 'a> for &'a OwnedVector {
         self.as_ref()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_padded() {
+        let vec = Vector::new(&[1.0, 2.0, 3.0], "a");
+
+        let padded = vec.padded(5);
+        assert_eq!(padded.data(), &[1.0, 2.0, 3.0, 0.0, 0.0]);
+        assert_eq!(padded.term(), "a");
+
+        let truncated = vec.padded(2);
+        assert_eq!(truncated.data(), &[1.0, 2.0]);
+        assert_eq!(truncated.term(), "a");
+
+        assert_eq!(vec.padded(3).as_ref(), vec);
+    }
+
+    #[test]
+    fn test_geometric_mean() {
+        let a = Vector::new(&[1.0, 4.0, -2.0, 2.0, 0.0], "a");
+        let b = Vector::new(&[4.0, 1.0, -8.0, -9.0, 3.0], "b");
+        let c = Vector::new(&[2.0, 2.0, -4.0, 1.5, 1.0], "c");
+
+        let mean = OwnedVector::geometric_mean(&[a, b, c]).unwrap();
+        assert_eq!(mean.term(), "a b c");
+        let exp = [2.0, 2.0, -4.0, -3.0, 0.0];
+        for (got, exp) in mean.data().iter().zip(exp) {
+            assert!((got - exp).abs() < 1e-5, "{got} != {exp}");
+        }
+
+        assert_eq!(OwnedVector::geometric_mean(&[]), None);
+    }
+
+    #[test]
+    fn test_cosine_batch() {
+        let query = Vector::new(&[1.0, 2.0, 3.0], "q");
+        let candidates = [
+            Vector::new(&[1.0, 0.07, 23.1], "a"),
+            Vector::new(&[0.13, -3.19, 3.12], "b"),
+            Vector::new(&[0.0, 0.0, 0.0], "c"),
+        ];
+
+        let scores = query.cosine_batch(&candidates);
+        assert_eq!(scores.len(), candidates.len());
+        for (score, candidate) in scores.iter().zip(candidates.iter()) {
+            assert_eq!(*score, query.cosine(candidate));
+        }
+    }
+
+    #[test]
+    fn test_hamming() {
+        let a = Vector::new(&[1.0, -2.0, 3.0, -0.5], "a");
+        let b = Vector::new(&[0.3, 2.0, -3.0, -1.5], "b");
+
+        assert_eq!(a.hamming(&b), 2);
+        assert_eq!(b.hamming(&a), 2);
+        assert_eq!(a.hamming(&a), 0);
+    }
+
+    #[test]
+    fn test_projection() {
+        let vec = Vector::new(&[3.0, -2.0, 5.0], "a");
+        let axis = Vector::new(&[0.0, 2.0, 0.0], "axis");
+
+        let proj = vec.project_onto(&axis);
+        assert_eq!(proj.data(), &[0.0, -2.0, 0.0]);
+        assert_eq!(proj.term(), "a");
+
+        let rej = vec.reject_from(&axis);
+        assert_eq!(rej.data(), &[3.0, 0.0, 5.0]);
+        assert_eq!(rej.term(), "a");
+        assert_eq!(rej.dot(&axis), 0.0);
+
+        let zero = Vector::new(&[0.0, 0.0, 0.0], "zero");
+        assert_eq!(vec.project_onto(&zero).data(), &[0.0, 0.0, 0.0]);
+        assert_eq!(vec.reject_from(&zero).data(), vec.data());
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let vec = OwnedVector::from_iter_with_term("a".to_string(), (0..4).map(|i| i as f32 * 0.5));
+        assert_eq!(vec.data(), &[0.0, 0.5, 1.0, 1.5]);
+        assert_eq!(vec.term(), "a");
+
+        let vec: OwnedVector = (1..=3).map(|i| i as f32).collect();
+        assert_eq!(vec.data(), &[1.0, 2.0, 3.0]);
+        assert_eq!(vec.term(), "");
+    }
+
+    #[test]
+    #[cfg(feature = "base64")]
+    fn test_base64() {
+        let vec = Vector::new(&[1.0, -2.5, 3.25e-7], "a");
+
+        let encoded = vec.to_base64();
+        let decoded = OwnedVector::from_base64("a".to_string(), &encoded).unwrap();
+        assert_eq!(decoded.as_ref(), vec);
+        assert_eq!(decoded.to_base64(), encoded);
+
+        let res = OwnedVector::from_base64("a".to_string(), "AAAA");
+        assert_eq!(res, Err(Error::InvalidVectorFormat));
+        let res = OwnedVector::from_base64("a".to_string(), "not base64!");
+        assert_eq!(res, Err(Error::InvalidVectorFormat));
+    }
+
+    #[test]
+    fn test_checked_cosine() {
+        let a = Vector::new(&[1.0, 2.0, 3.0], "a");
+        let b = Vector::new(&[2.0, 4.0, 6.0], "b");
+        assert_eq!(a.checked_cosine(&b), Ok(a.cosine(&b)));
+
+        let c = Vector::new(&[1.0, 2.0], "c");
+        assert_eq!(a.checked_cosine(&c), Err(Error::DimMismatch(2, 3)));
+        assert_eq!(c.checked_cosine(&a), Err(Error::DimMismatch(3, 2)));
+    }
+
+    #[test]
+    fn test_clamp() {
+        let vec = Vector::new(&[0.5, -3.0, 12.0], "a");
+        let clamped = vec.clamp(-1.0, 1.0);
+        assert_eq!(clamped.as_ref(), Vector::new(&[0.5, -1.0, 1.0], "a"));
+        assert_eq!(clamped.clamp(-1.0, 1.0), clamped);
+    }
+
+    #[test]
+    fn test_with_term() {
+        let a = Vector::new(&[1.0, 2.0], "a");
+        let b = Vector::new(&[3.0, 4.0], "b");
+
+        let sum = a + b;
+        assert_eq!(sum.term(), "a b");
+
+        let renamed = sum.with_term("sum");
+        assert_eq!(renamed.term(), "sum");
+        assert_eq!(renamed.data(), sum.data());
+
+        let renamed = a.with_term("x");
+        assert_eq!(renamed.as_ref(), Vector::new(&[1.0, 2.0], "x"));
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let a = Vector::new(&[1.0, 2.0, 3.0], "a");
+        let b = Vector::new(&[1.0, 2.000001, 3.0], "a");
+
+        assert_ne!(a, b);
+        assert!(a.approx_eq(&b, 1e-5));
+        assert!(!a.approx_eq(&b, 1e-8));
+
+        assert!(!a.approx_eq(&Vector::new(&[1.0, 2.0, 3.0], "b"), 1e-5));
+        assert!(!a.approx_eq(&Vector::new(&[1.0, 2.0], "a"), 1e-5));
+        assert!(a.with_term("a").approx_eq(&b, 1e-5));
+    }
+
+    #[test]
+    fn test_from_owned_parts() {
+        let mut data = Vec::with_capacity(10);
+        data.extend([1.0, 2.0, 3.0]);
+        let ptr = data.as_ptr();
+
+        let vec = OwnedVector::from(("a".to_string(), data));
+        assert_eq!(vec.as_ref(), Vector::new(&[1.0, 2.0, 3.0], "a"));
+        assert_eq!(vec.data.capacity(), 10);
+        assert_eq!(vec.data.as_ptr(), ptr);
+    }
+}